@@ -1,7 +1,6 @@
 use std::env;
 use std::path::PathBuf;
 
-#[cfg(windows)]
 fn main() {
     // CEF
     let cef_path: PathBuf = env::var("CEF_PATH")
@@ -10,7 +9,12 @@ fn main() {
     assert!(cef_path.exists());
     let cef_lib_path = cef_path.join("Release");
     println!("cargo:rustc-link-search={}", cef_lib_path.display());
-    println!("cargo:rustc-link-lib=libcef");
+    // the build script runs on the host, so ask cargo what we're building for
+    match env::var("CARGO_CFG_TARGET_OS").as_ref().map(String::as_str) {
+        Ok("windows") => println!("cargo:rustc-link-lib=libcef"),
+        Ok("linux") => println!("cargo:rustc-link-lib=cef"),
+        target => panic!("unsupported target OS {:?}, only Windows and Linux are", target),
+    }
 
     // Generate CEF bindings
     let bindings_path = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())
@@ -43,15 +47,20 @@ fn main() {
             .whitelist_type("cef_context_menu_handler_t")
             .whitelist_type("cef_context_menu_params_t")
             .whitelist_type("cef_display_handler_t")
+            .whitelist_type("cef_focus_handler_t")
+            .whitelist_type("cef_drag_handler_t")
+            .whitelist_type("cef_drag_data_t")
             .whitelist_type("cef_frame_t")
             .whitelist_type("cef_menu_model_t")
             .whitelist_type("cef_life_span_handler_t")
             .whitelist_type("cef_binary_value_t")
             .whitelist_type("cef_errorcode_t")
             .whitelist_type("cef_request_callback_t")
+            .whitelist_type("cef_auth_callback_t")
             .whitelist_type("cef_request_handler_t")
             .whitelist_type("cef_sslinfo_t")
             .whitelist_type("cef_x509certificate_t")
+            .whitelist_type("cef_x509cert_principal_t")
             .whitelist_type("cef_browser_settings_t")
             .whitelist_type("cef_browser_view_create")
             .whitelist_type("cef_browser_view_delegate_t")
@@ -68,6 +77,22 @@ fn main() {
             .whitelist_type("cef_window_handle_t")
             .whitelist_type("cef_window_info_t")
             .whitelist_type("cef_dictionary_value_t")
+            .whitelist_type("cef_dev_tools_message_observer_t")
+            .whitelist_type("cef_registration_t")
+            .whitelist_type("cef_value_t")
+            .whitelist_type("cef_cookie_manager_t")
+            .whitelist_type("cef_cookie_t")
+            .whitelist_type("cef_cookie_visitor_t")
+            .whitelist_type("cef_completion_callback_t")
+            .whitelist_type("cef_set_cookie_callback_t")
+            .whitelist_type("cef_delete_cookies_callback_t")
+            .whitelist_function("cef_cookie_manager_get_global_manager")
+            .whitelist_function("cef_time_to_timet")
+            .whitelist_function("cef_time_from_timet")
+            .whitelist_function("cef_value_create")
+            .whitelist_function("cef_list_value_create")
+            .whitelist_function("cef_string_list_free")
+            .whitelist_function("cef_string_utf16_clear")
             .whitelist_type("cef_value_type_t")
             .whitelist_type("cef_v8value_t")
             .whitelist_type("cef_string_list_t")
@@ -76,13 +101,22 @@ fn main() {
             .whitelist_function("cef_string_list_value")
             .whitelist_function("cef_string_list_size")
             .whitelist_function("cef_v8value_create_string")
+            .whitelist_function("cef_v8value_create_bool")
             .whitelist_type("cef_file_dialog_mode_t")
             .whitelist_type("cef_run_file_dialog_callback_t")
+            .whitelist_type("cef_dialog_handler_t")
+            .whitelist_type("cef_file_dialog_callback_t")
             .whitelist_function("cef_register_extension")
             .whitelist_function("cef_process_message_create")
             .whitelist_type("cef_process_message_t")
             .whitelist_type("cef_request_context_t")
             .whitelist_type("cef_browser_process_handler_t")
+            .whitelist_type("cef_print_handler_t")
+            .whitelist_type("cef_print_settings_t")
+            .whitelist_type("cef_print_dialog_callback_t")
+            .whitelist_type("cef_print_job_callback_t")
+            .whitelist_type("cef_range_t")
+            .whitelist_function("cef_print_settings_create")
             .whitelist_type("cef_render_process_handler_t")
             .whitelist_type("cef_v8context_t")
             .whitelist_type("cef_v8handler_t")
@@ -93,13 +127,21 @@ fn main() {
             .whitelist_type("cef_v8context_t")
             .whitelist_function("cef_do_message_loop_work")
             .whitelist_function("cef_enable_highdpi_support")
+            .whitelist_function("cef_get_xdisplay")
             .whitelist_function("cef_initialize")
             .whitelist_function("cef_browser_view_create")
             .whitelist_function("cef_execute_process")
             .whitelist_function("cef_quit_message_loop")
+            .whitelist_type("cef_task_t")
+            .whitelist_type("cef_thread_id_t")
+            .whitelist_function("cef_post_task")
+            .whitelist_function("cef_post_delayed_task")
+            .whitelist_function("cef_currently_on")
             .whitelist_function("cef_dictionary_value_create")
             .whitelist_function("cef_image_create")
             .whitelist_function("cef_request_context_get_global_context")
+            .whitelist_function("cef_request_context_create_context")
+            .whitelist_type("cef_request_context_settings_t")
             .whitelist_function("cef_string_utf8_to_utf16")
             .whitelist_function("cef_string_userfree_t")
             .whitelist_function("cef_string_userfree_alloc")
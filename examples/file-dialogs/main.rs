@@ -1,15 +1,21 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+#[cfg(windows)]
 use std::{mem, ptr};
+#[cfg(windows)]
 use winapi::shared::minwindef::{LPARAM, UINT, WPARAM, LRESULT, HINSTANCE};
+#[cfg(windows)]
 use winapi::shared::windef::HWND;
 
+#[cfg(windows)]
 static mut H_INSTANCE: HINSTANCE = ptr::null_mut();
 
+#[cfg(windows)]
 struct WindowData {
     browser: cef_client::Browser,
 }
 
+#[cfg(windows)]
 unsafe extern "system" fn wndproc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
     use winapi::um::winuser::{WM_SIZE, WM_ERASEBKGND, WM_CLOSE, WM_DESTROY, DestroyWindow, PostQuitMessage, DefWindowProcW, GetWindowLongPtrW, GetClientRect };
 
@@ -72,6 +78,7 @@ unsafe extern "system" fn wndproc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam:
     }
 }
 
+#[cfg(windows)]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     unsafe {
         H_INSTANCE = winapi::um::libloaderapi::GetModuleHandleW(ptr::null_mut());
@@ -91,7 +98,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .apply()?;
 
     // initialize CEF
-    let mut cef = cef_client::Cef::initialize(Some(8822), true)?;
+    let mut cef = cef_client::Cef::initialize(Some(8822), true, cef_client::MessageLoopMode::ExternalPump)?;
 
     // load our icon
     use winapi::um::winuser::{MAKEINTRESOURCEW, LoadImageW, IMAGE_ICON, LR_DEFAULTSIZE};
@@ -102,7 +109,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     // create our window class
-    use winapi::um::winuser::{WNDCLASSW, CS_HREDRAW, CS_VREDRAW, LoadCursorW, IDC_ARROW, RegisterClassW, CreateWindowExW, WS_OVERLAPPEDWINDOW, ShowWindow, SW_SHOW, PeekMessageW, WM_QUIT, PM_REMOVE, TranslateMessage, DispatchMessageW, SetWindowLongPtrW, GetDesktopWindow, GetWindowRect, SetWindowPos, HWND_TOP, GetClientRect };
+    use winapi::um::winuser::{WNDCLASSW, CS_HREDRAW, CS_VREDRAW, LoadCursorW, IDC_ARROW, RegisterClassW, CreateWindowExW, WS_OVERLAPPEDWINDOW, ShowWindow, SW_SHOW, SetWindowLongPtrW, GetDesktopWindow, GetWindowRect, SetWindowPos, HWND_TOP, GetClientRect };
     use winapi::shared::windef::HBRUSH;
     let class_name: Vec<u16> = "cef-win-file-dialogs\0".encode_utf16().collect();
     let wnd_class = WNDCLASSW {
@@ -153,7 +160,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // create the browser
     unsafe { GetClientRect(hwnd, &mut rect); }
     use urlencoding::encode;
-    let browser = cef.create_browser("my_cef_window", hwnd, &format!("data:text/html,{}", encode(include_str!("page.html"))), rect.right - rect.left, rect.bottom - rect.top);
+    let browser = cef.create_browser("my_cef_window", &cef_client::RawWindow::from_hwnd(hwnd as _), &format!("data:text/html,{}", encode(include_str!("page.html"))), rect.right - rect.left, rect.bottom - rect.top, None)?;
 
     // and give the window our data struct
     let mut data: WindowData = WindowData {
@@ -168,48 +175,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         );
     }
 
-    // finally, the message loop
-    unsafe {
-        let mut msg = mem::MaybeUninit::uninit();
-        let mut last_process_time: std::time::Instant = std::time::Instant::now();
-        let frame_period = std::time::Duration::from_secs_f64(1.0 / 60.0);
-        'mainloop: loop {
-            // use a more "gaming" type loop so that we can process CEF work at least at 60 fps
-            'peekloop: loop {
-                let message_available = PeekMessageW(msg.as_mut_ptr(), ptr::null_mut(), 0, 0, PM_REMOVE);
-                if message_available != 0 {
-                    let msg = msg.assume_init();
-
-                    if msg.message == WM_QUIT {
-                        log::debug!("time to quit!");
-                        break 'mainloop;
-                    }
-
-                    TranslateMessage(&msg);
-                    DispatchMessageW(&msg);
-                }
-                else {
-                    break 'peekloop;
-                }
-            }
-
-            // let CEF do its thing
-            if cef.should_do_work() {
-                cef.do_message_loop_work();
-            }
-
-            // make sure we run through the loop at least at 60 fps
-            let now = std::time::Instant::now();
-            let duration = now.duration_since(last_process_time);
-            last_process_time = now;
-            let sleep_time = frame_period.checked_sub(duration);
-            if let Some(sleep_time) = sleep_time {
-                std::thread::sleep(sleep_time);
-            }
-        }
-    }
+    // finally, run the message loop until the window is destroyed
+    cef.run();
 
     log::info!("shutting down...");
 
     Ok(())
 }
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("this example builds its window with the Win32 API, so it only runs on Windows");
+}
@@ -56,9 +56,14 @@ pub fn allocate(schedule: Arc<Schedule>) -> *mut App {
         render_process_handler: render_process_handler::allocate(),
     };
 
+    super::ref_counting::allocated("App");
     Box::into_raw(Box::from(app))
 }
 
+pub unsafe fn set_print_backend(slf: *mut App, backend: Box<dyn super::PrintBackend + Send>) {
+    browser_process_handler::set_print_backend((*slf).browser_process_handler, backend);
+}
+
 extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
     let app = base as *mut App;
     unsafe {
@@ -73,9 +78,10 @@ extern "C" fn release(base: *mut cef_base_ref_counted_t) -> i32 {
     if count == 0 {
         unsafe {
             let app: Box<App> = Box::from_raw(app as *mut App);
-            // TODO: free our handlers here too
-            Box::from_raw(app.browser_process_handler);
-            Box::from_raw(app.render_process_handler);
+            super::ref_counting::freed("App");
+            // CEF may still hold references to our handlers, so only give up ours
+            super::ref_counting::release(app.browser_process_handler);
+            super::ref_counting::release(app.render_process_handler);
         }
         1
     } else {
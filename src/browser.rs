@@ -0,0 +1,572 @@
+use super::bindings::{
+    cef_browser_host_create_browser_sync, cef_browser_host_t, cef_browser_settings_t,
+    cef_browser_t, cef_dictionary_value_create, cef_request_context_get_global_context,
+    cef_state_t_STATE_DISABLED, cef_string_list_alloc, cef_string_list_append, cef_string_t,
+    cef_string_utf16_clear, cef_string_utf8_to_utf16, cef_window_handle_t,
+};
+use super::print_pdf_callback;
+use super::value::to_cef_string;
+use super::run_file_dialog_callback;
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+use std::ffi::CString;
+
+#[cfg(windows)]
+#[path = "browser_windows.rs"]
+mod platform;
+#[cfg(target_os = "linux")]
+#[path = "browser_linux.rs"]
+mod platform;
+
+pub(crate) use platform::resize_window;
+
+/// The browser, keeping track of everything including its host. Dropping it
+/// only gives up our references; use `close` to actually close the browser
+pub struct Browser {
+    browser: *mut cef_browser_t,
+    client: *mut super::client::Client,
+    host: *mut cef_browser_host_t,
+    window: cef_window_handle_t,
+}
+
+impl super::Cef {
+    /// Create the browser as a child of a native window, which must be a
+    /// Win32 window on Windows and an Xlib or Xcb window on Linux. Browsers
+    /// use the global request context unless given one. This must be called
+    /// on the UI thread, so not from the main thread when using
+    /// `MessageLoopMode::MultiThreaded`
+    pub fn create_browser<W: HasRawWindowHandle>(
+        &mut self,
+        window_name: &str,
+        parent_window: &W,
+        url: &str,
+        width: i32,
+        height: i32,
+        request_context: Option<&super::RequestContext>,
+    ) -> Result<Browser, Box<dyn std::error::Error>> {
+        let parent_window = platform::parent_window(parent_window.raw_window_handle())?;
+
+        let mut cef_window_name = cef_string_t::default();
+        let window_name = window_name.as_bytes();
+        let window_name = CString::new(window_name).unwrap();
+        unsafe {
+            cef_string_utf8_to_utf16(
+                window_name.as_ptr(),
+                window_name.to_bytes().len() as u64,
+                &mut cef_window_name,
+            );
+        }
+        let mut window_info = platform::window_info(cef_window_name, parent_window, width, height);
+        let client = super::client::allocate(self.registry.clone());
+        // one reference goes to CEF, the other stays with the `Browser`
+        unsafe { super::ref_counting::add_ref(client) };
+        let mut cef_url = cef_string_t::default();
+        let url = url.as_bytes();
+        let url = CString::new(url).unwrap();
+        unsafe {
+            cef_string_utf8_to_utf16(url.as_ptr(), url.to_bytes().len() as u64, &mut cef_url);
+        }
+        let mut browser_settings = cef_browser_settings_t::default();
+        browser_settings.databases = cef_state_t_STATE_DISABLED;
+        browser_settings.local_storage = cef_state_t_STATE_DISABLED;
+        browser_settings.application_cache = cef_state_t_STATE_DISABLED;
+        let browser = unsafe {
+            cef_browser_host_create_browser_sync(
+                &window_info,
+                client as *mut super::bindings::cef_client_t,
+                &cef_url,
+                &browser_settings,
+                cef_dictionary_value_create(),
+                match request_context {
+                    Some(context) => context.to_raw(),
+                    None => cef_request_context_get_global_context(),
+                },
+            )
+        };
+        unsafe {
+            cef_string_utf16_clear(&mut window_info.window_name);
+            cef_string_utf16_clear(&mut cef_url);
+        }
+        if browser.is_null() {
+            unsafe { super::ref_counting::release(client) };
+            return Err(Box::from("failed to create browser"));
+        }
+
+        let host = unsafe { (*browser).get_host.unwrap()(browser) };
+        let window = unsafe { (*host).get_window_handle.unwrap()(host) };
+        let browser = Browser {
+            browser,
+            client,
+            host,
+            window,
+        };
+        Ok(browser)
+    }
+}
+
+impl super::Cef {
+    /// Every browser that is currently alive, including popups. Handles can
+    /// be used from any thread, unlike `Browser`
+    pub fn browsers(&self) -> Vec<super::BrowserHandle> {
+        self.registry
+            .browsers()
+            .into_iter()
+            .map(|browser| unsafe { handle_from_registry(browser) })
+            .collect()
+    }
+
+    /// Find a live browser by its id, i.e. one given to a callback
+    pub fn browser_by_id(&self, id: i32) -> Option<super::BrowserHandle> {
+        self.registry
+            .find(id)
+            .map(|browser| unsafe { handle_from_registry(browser) })
+    }
+
+    /// Listen for browsers being created, including popups. The listener is
+    /// called on the UI thread
+    pub fn set_browser_created_listener<F: FnMut(&Browser) + Send + 'static>(&self, listener: F) {
+        self.registry.set_created_listener(Box::from(listener));
+    }
+
+    /// Listen for any browser having been closed and destroyed
+    pub fn set_browser_destroyed_listener<F: FnMut(&super::BrowserClosed) + Send + 'static>(&self, listener: F) {
+        self.registry.set_destroyed_listener(Box::from(listener));
+    }
+}
+
+/// Turn a reference the registry handed out into a handle, which takes its own
+/// references. Only thread-safe CEF calls are made here
+unsafe fn handle_from_registry(browser: *mut cef_browser_t) -> super::BrowserHandle {
+    let host = (*browser).get_host.expect("get_host is a function")(browser);
+    let client = (*host).get_client.expect("get_client is a function")(host) as *mut super::client::Client;
+    let handle = super::BrowserHandle::new(browser, client);
+    super::ref_counting::release(client);
+    super::ref_counting::release(host);
+    super::ref_counting::release(browser);
+    handle
+}
+
+impl Browser {
+    /// Wrap a browser reference handed to us by CEF. The browser must have
+    /// been created with one of our clients
+    pub(crate) unsafe fn from_raw(browser: *mut cef_browser_t) -> Browser {
+        let host = (*browser).get_host.unwrap()(browser);
+        let client = (*host).get_client.unwrap()(host) as *mut super::client::Client;
+        let window = (*host).get_window_handle.unwrap()(host);
+        Browser {
+            browser,
+            client,
+            host,
+            window,
+        }
+    }
+
+    /// The browser's unique id within this process
+    pub fn id(&self) -> i32 {
+        unsafe { (*self.browser).get_identifier.unwrap()(self.browser) }
+    }
+
+    /// Get a handle for controlling this browser from other threads
+    pub fn handle(&self) -> super::BrowserHandle {
+        unsafe { super::BrowserHandle::new(self.browser, self.client) }
+    }
+
+    /// Load `url` in the main frame
+    pub fn navigate(&self, url: &str) {
+        unsafe { super::browser_handle::navigate(self.browser, url) };
+    }
+
+    /// Evaluate JavaScript in the main frame, resolving to its result as
+    /// JSON. Promises are awaited and exceptions become errors
+    pub fn eval(
+        &self,
+        code: &str,
+    ) -> impl std::future::Future<Output = Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>>> {
+        unsafe { super::eval::evaluate(self.client, self.host, code) }
+    }
+
+    /// Whether both refer to the same underlying browser
+    pub fn is_same(&self, other: &Browser) -> bool {
+        unsafe { (*self.browser).is_same.unwrap()(self.browser, other.browser) == 1 }
+    }
+
+    pub fn set_fullscreen_listener<F: FnMut(bool) + 'static>(&self, listener: F) {
+        unsafe {
+            super::client::set_fullscreen_listener(self.client, listener);
+        }
+    }
+
+    /// Listen for the browser handing focus back to the host, i.e. when the
+    /// user tabs out of the page. The argument is `true` when focus should
+    /// move to the next control, `false` for the previous one
+    pub fn set_take_focus_listener<F: FnMut(bool) + 'static>(&self, listener: F) {
+        unsafe {
+            super::client::set_take_focus_listener(self.client, listener);
+        }
+    }
+
+    /// Decide whether the browser may take focus; return `false` from the
+    /// listener to keep focus where it is
+    pub fn set_focus_requested_listener<F: FnMut(super::FocusSource) -> bool + 'static>(&self, listener: F) {
+        unsafe {
+            super::client::set_focus_requested_listener(self.client, listener);
+        }
+    }
+
+    /// Listen for the browser receiving focus
+    pub fn set_got_focus_listener<F: FnMut() + 'static>(&self, listener: F) {
+        unsafe {
+            super::client::set_got_focus_listener(self.client, listener);
+        }
+    }
+
+    /// Give keyboard focus to the browser, or take it away
+    pub fn set_focus(&self, focus: bool) {
+        unsafe {
+            (*self.host).set_focus.unwrap()(self.host, focus as i32);
+        }
+    }
+
+    /// Choose which kinds of drags the browser accepts
+    pub fn set_drag_policy(&self, policy: super::DragPolicy) {
+        unsafe {
+            super::client::set_drag_policy(self.client, policy);
+        }
+    }
+
+    /// Listen for things being dragged into the browser. Only called for drags
+    /// the drag policy allows; return `false` from the listener to reject it
+    pub fn set_drag_enter_listener<F: FnMut(&super::DragEnter) -> bool + 'static>(&self, listener: F) {
+        unsafe {
+            super::client::set_drag_enter_listener(self.client, listener);
+        }
+    }
+
+    /// Decide what to do about certificate errors. Without a policy, pages
+    /// with certificate errors fail to load
+    pub fn set_certificate_policy(&self, policy: super::CertificatePolicy) {
+        unsafe {
+            super::client::set_certificate_policy(self.client, policy);
+        }
+    }
+
+    /// Supply credentials when a server or proxy asks for them. Without a
+    /// provider, authentication requests are cancelled
+    pub fn set_credential_provider<P: super::CredentialProvider + 'static>(&self, provider: P) {
+        unsafe {
+            super::client::set_credential_provider(self.client, provider);
+        }
+    }
+
+    /// Answer file dialogs from `<input type="file">` instead of showing
+    /// Chromium's built-in one
+    pub fn set_file_dialog_handler<H: super::FileDialogHandler + 'static>(&self, handler: H) {
+        unsafe {
+            super::client::set_file_dialog_handler(self.client, handler);
+        }
+    }
+
+    /// Listen for the browser's render process crashing or being killed
+    pub fn set_render_process_terminated_listener<F: FnMut(&super::RenderProcessTerminated) + 'static>(&self, listener: F) {
+        unsafe {
+            super::client::set_render_process_terminated_listener(self.client, listener);
+        }
+    }
+
+    /// Choose how to recover from the render process terminating. Without
+    /// recovery, the browser is left blank
+    pub fn set_crash_recovery(&self, crash_recovery: super::CrashRecovery) {
+        unsafe {
+            super::client::set_crash_recovery(self.client, crash_recovery);
+        }
+    }
+
+    /// Resize the browser window, call this whenever the host resizes
+    pub fn resize(&self, width: i32, height: i32) {
+        unsafe {
+            (*self.host).notify_move_or_resize_started.unwrap()(self.host);
+            resize_window(self.window, width, height);
+            (*self.host).was_resized.unwrap()(self.host);
+        }
+    }
+
+    /// Tell the browser that the screen's scale factor or layout changed
+    pub fn notify_screen_info_changed(&self) {
+        unsafe {
+            (*self.host).notify_screen_info_changed.unwrap()(self.host);
+        }
+    }
+
+    /// Close the browser instance. Call this from the top-level window's
+    /// `WM_CLOSE` handler and only destroy the window once it returns `true`
+    pub fn try_close(&self) -> bool {
+        let closed = unsafe { (*self.host).try_close_browser.unwrap()(self.host) };
+        closed == 1
+    }
+
+    /// Close the browser, resolving once it has been destroyed. Unless forced,
+    /// the page's `onbeforeunload` handler runs first and may cancel closing,
+    /// in which case the future doesn't resolve until the browser is closed
+    /// some other way
+    pub fn close(
+        &self,
+        force: bool,
+    ) -> impl std::future::Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> {
+        let receiver = unsafe {
+            let receiver = super::client::wait_for_close(self.client, self.id());
+            if receiver.is_some() {
+                (*self.host).close_browser.unwrap()(self.host, force as i32);
+            }
+            receiver
+        };
+        async move {
+            match receiver {
+                Some(receiver) => receiver.await.map_err(|_| Box::from("browser went away before closing")),
+                // already closed
+                None => Ok(()),
+            }
+        }
+    }
+
+    /// Whether the browser has agreed to close and is on its way out
+    pub fn is_closing(&self) -> bool {
+        unsafe { super::client::is_closing(self.client, self.id()) }
+    }
+
+    /// Listen for the browser having been closed and destroyed
+    pub fn set_closed_listener<F: FnMut(&super::BrowserClosed) + 'static>(&self, listener: F) {
+        unsafe {
+            super::client::set_closed_listener(self.client, listener);
+        }
+    }
+
+    pub unsafe fn print_to_pdf_pointer<P: AsRef<std::path::Path>>(
+        browser: *mut cef_browser_t,
+        path: P,
+        settings: &super::PdfPrintSettings,
+        on_done: Option<Box<dyn FnMut(bool)>>,
+    ) {
+        log::debug!("printing PDF to path `{}`...", path.as_ref().display());
+
+        // get our browser host
+        let host = (*browser).get_host.unwrap()(browser);
+
+        // first, convert the path to a cef string
+        let path: String = path.as_ref().display().to_string();
+        let path = path.as_bytes();
+        let path = CString::new(path).unwrap();
+        let mut cef_path = cef_string_t::default();
+        cef_string_utf8_to_utf16(path.as_ptr(), path.to_bytes().len() as u64, &mut cef_path);
+
+        // determine the settings
+        let mut cef_settings = settings.to_cef();
+
+        // now a callback when the print is done
+        let callback = print_pdf_callback::allocate(on_done);
+
+        // finally, initiate the print
+        (*host).print_to_pdf.expect("print_to_pdf is a function")(
+            host,
+            &mut cef_path,
+            &cef_settings,
+            callback as *mut super::bindings::_cef_pdf_print_callback_t,
+        );
+        cef_string_utf16_clear(&mut cef_path);
+        super::PdfPrintSettings::clear_cef(&mut cef_settings);
+        super::ref_counting::release(host);
+    }
+
+    /// Print to a temporary PDF file, handing its contents to `on_done` once
+    /// the file has been read back and removed
+    pub unsafe fn print_to_pdf_bytes_pointer(
+        browser: *mut cef_browser_t,
+        settings: &super::PdfPrintSettings,
+        on_done: Box<dyn FnOnce(Result<Vec<u8>, String>)>,
+    ) {
+        // a private directory with a random name, so nobody else can plant
+        // or read the file; it's removed whenever the callback goes away
+        let dir = match tempfile::Builder::new().prefix("cef-print-").tempdir() {
+            Ok(dir) => dir,
+            Err(e) => {
+                on_done(Err(format!("failed to create a temporary directory: {}", e)));
+                return;
+            }
+        };
+        let path = dir.path().join("print.pdf");
+
+        let printed = path.clone();
+        let mut dir = Some(dir);
+        let mut on_done = Some(on_done);
+        Browser::print_to_pdf_pointer(
+            browser,
+            path,
+            settings,
+            Some(Box::from(move |ok: bool| {
+                let result = if ok {
+                    std::fs::read(&printed).map_err(|e| format!("failed to read printed PDF: {}", e))
+                } else {
+                    Err("failed to print to PDF".to_owned())
+                };
+                if let Some(dir) = dir.take() {
+                    if let Err(e) = dir.close() {
+                        log::warn!("failed to remove the printed PDF: {}", e);
+                    }
+                }
+                if let Some(on_done) = on_done.take() {
+                    on_done(result);
+                }
+            })),
+        );
+    }
+
+    pub unsafe fn run_file_dialog_pointer(
+        browser: *mut cef_browser_t,
+        options: &super::FileDialogOptions,
+        on_done: Option<Box<dyn FnMut(Vec<std::path::PathBuf>)>>,
+    ) {
+        log::debug!("launching file dialog...");
+
+        // get our browser host
+        let host = (*browser).get_host.unwrap()(browser);
+
+        let mut cef_title = to_cef_string(&options.title);
+        let default_path = options
+            .default_path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        let mut cef_default_path = to_cef_string(&default_path);
+
+        // build the filter list
+        let filters = cef_string_list_alloc();
+        for filter in options.filters.iter() {
+            let mut cef_filter = to_cef_string(&filter.to_cef_format());
+            cef_string_list_append(filters, &cef_filter);
+            cef_string_utf16_clear(&mut cef_filter);
+        }
+
+        // and a callback
+        let callback = run_file_dialog_callback::allocate(on_done);
+
+        // and run the dialog
+        (*host)
+            .run_file_dialog
+            .expect("run_file_dialog is a function")(
+            host,
+            options.cef_mode(),
+            &cef_title,
+            &cef_default_path,
+            filters,
+            options.selected_filter as i32,
+            callback as *mut super::bindings::_cef_run_file_dialog_callback_t,
+        );
+        cef_string_utf16_clear(&mut cef_title);
+        cef_string_utf16_clear(&mut cef_default_path);
+        super::bindings::cef_string_list_free(filters);
+        super::ref_counting::release(host);
+    }
+
+    /// Print the page to a PDF file at `path`
+    pub fn print_to_pdf<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        settings: &super::PdfPrintSettings,
+        on_done: Option<Box<dyn FnMut(bool)>>,
+    ) {
+        unsafe {
+            Browser::print_to_pdf_pointer(self.browser, path, settings, on_done);
+        }
+    }
+
+    /// Print the page like `window.print()` does, through the system print
+    /// dialog or the `PrintBackend` given to `Cef::set_print_backend`
+    pub fn print(&self) {
+        unsafe {
+            (*self.host).print.unwrap()(self.host);
+        }
+    }
+
+    /// Print the page to a PDF in memory, without leaving anything on disk
+    pub fn print_to_pdf_bytes(
+        &self,
+        settings: &super::PdfPrintSettings,
+    ) -> impl std::future::Future<Output = Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>> {
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        unsafe {
+            Browser::print_to_pdf_bytes_pointer(
+                self.browser,
+                settings,
+                Box::from(move |result| {
+                    let _ = sender.send(result);
+                }),
+            );
+        }
+        async move {
+            match receiver.await {
+                Ok(result) => result.map_err(Box::from),
+                Err(_) => Err(Box::from("print was cancelled")),
+            }
+        }
+    }
+
+    /// Get the request context this browser belongs to
+    pub fn request_context(&self) -> Option<super::RequestContext> {
+        unsafe {
+            super::RequestContext::from_raw((*self.host).get_request_context.unwrap()(self.host))
+        }
+    }
+
+    /// Get the cookie manager of the request context this browser belongs to
+    pub fn cookie_manager(&self) -> Option<super::CookieManager> {
+        self.request_context()
+            .and_then(|context| context.cookie_manager())
+    }
+
+    /// Capture a screenshot of the page, resolving to the encoded image. The
+    /// returned future must be polled on the UI thread
+    pub fn capture_screenshot(
+        &self,
+        options: &super::screenshot::ScreenshotOptions,
+    ) -> impl std::future::Future<Output = Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>> {
+        unsafe { super::screenshot::capture(self.client, self.host, options.clone()) }
+    }
+
+    /// Print the page to a PDF in memory through DevTools, which unlike
+    /// `print_to_pdf` supports page ranges, HTML header and footer templates
+    /// and the page's CSS page size. The returned future must be polled on the
+    /// UI thread
+    pub fn print_to_pdf_dev_tools(
+        &self,
+        options: &super::DevToolsPdfOptions,
+    ) -> impl std::future::Future<Output = Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>> {
+        unsafe { super::dev_tools_pdf::print(self.client, self.host, options.clone()) }
+    }
+
+    /// Show a file dialog, calling `on_done` with the chosen paths, which are
+    /// empty if the dialog was cancelled
+    pub fn run_file_dialog(
+        &self,
+        options: &super::FileDialogOptions,
+        on_done: Option<Box<dyn FnMut(Vec<std::path::PathBuf>)>>,
+    ) {
+        unsafe {
+            Browser::run_file_dialog_pointer(self.browser, options, on_done);
+        }
+    }
+}
+
+unsafe impl HasRawWindowHandle for Browser {
+    /// The browser's own window, a child of the parent window it was created in
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        platform::raw_window_handle(self.window)
+    }
+}
+
+impl Drop for Browser {
+    fn drop(&mut self) {
+        unsafe {
+            super::ref_counting::release(self.host);
+            super::ref_counting::release(self.browser);
+            super::ref_counting::release(self.client);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use futures::channel::oneshot;
+use serde_json::Value;
+
+use super::bindings::{cef_browser_host_t, cef_browser_t, cef_string_utf16_clear};
+use super::client::{self, Client};
+use super::pdf_print_settings::PdfPrintSettings;
+use super::task::{self, Thread};
+use super::value::to_cef_string;
+
+/// The pointers a handle shares between threads. CEF's reference counting is
+/// thread-safe and we only ever dereference them on the UI thread
+struct Shared {
+    browser: *mut cef_browser_t,
+    client: *mut Client,
+}
+
+unsafe impl Send for Shared {}
+unsafe impl Sync for Shared {}
+
+impl Shared {
+    unsafe fn host(&self) -> *mut cef_browser_host_t {
+        (*self.browser).get_host.expect("get_host is a function")(self.browser)
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        // the client owns our non-`Send` listeners, so it must only ever be
+        // freed on the UI thread
+        let browser = self.browser as usize;
+        let client = self.client as usize;
+        let release = move || unsafe {
+            super::ref_counting::release(browser as *mut cef_browser_t);
+            super::ref_counting::release(client as *mut Client);
+        };
+        if task::currently_on(Thread::UI) {
+            release();
+        } else if !task::post(Thread::UI, Box::from(release)) {
+            log::warn!("couldn't release browser handle on the UI thread, leaking it");
+        }
+    }
+}
+
+/// A handle for controlling a browser from any thread. Every call is run on
+/// the CEF UI thread and returns a future for its result. Unlike `Browser`,
+/// handles are `Send + Sync` and cheap to clone
+#[derive(Clone)]
+pub struct BrowserHandle {
+    shared: Arc<Shared>,
+}
+
+impl BrowserHandle {
+    /// Take new references to the browser and its client
+    pub(crate) unsafe fn new(browser: *mut cef_browser_t, client: *mut Client) -> BrowserHandle {
+        super::ref_counting::add_ref(browser);
+        super::ref_counting::add_ref(client);
+        BrowserHandle {
+            shared: Arc::new(Shared { browser, client }),
+        }
+    }
+
+    /// Run `f` on the UI thread, resolving to what it returns
+    fn run_on_ui<T, F>(&self, f: F) -> impl Future<Output = Result<T, Box<dyn std::error::Error + Send + Sync>>> + Send
+    where
+        T: Send + 'static,
+        F: FnOnce(&Shared) -> T + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let shared = self.shared.clone();
+        let posted = task::post(
+            Thread::UI,
+            Box::from(move || {
+                let _ = sender.send(f(&shared));
+            }),
+        );
+        async move {
+            if !posted {
+                return Err(Box::from("couldn't post to the UI thread, is CEF running?"));
+            }
+            receiver.await.map_err(|_| Box::from("UI thread task was dropped"))
+        }
+    }
+
+    pub fn id(&self) -> i32 {
+        // the identifier never changes, so it's safe to read from any thread
+        unsafe { (*self.shared.browser).get_identifier.expect("get_identifier is a function")(self.shared.browser) }
+    }
+
+    /// Load `url` in the main frame
+    pub fn navigate(&self, url: &str) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send {
+        let url = url.to_owned();
+        self.run_on_ui(move |shared| unsafe { navigate(shared.browser, &url) })
+    }
+
+    /// Evaluate JavaScript in the main frame, see `Browser::eval`
+    pub fn eval(&self, code: &str) -> impl Future<Output = Result<Value, Box<dyn std::error::Error + Send + Sync>>> + Send {
+        let code = code.to_owned();
+        let evaluating = self.run_on_ui(move |shared| unsafe {
+            let host = shared.host();
+            let result = super::eval::evaluate(shared.client, host, &code);
+            super::ref_counting::release(host);
+            result
+        });
+        async move { evaluating.await?.await }
+    }
+
+    /// Print the page to a PDF file at `path`
+    pub fn print_to_pdf<P: Into<PathBuf>>(
+        &self,
+        path: P,
+        settings: PdfPrintSettings,
+    ) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send {
+        let path = path.into();
+        let printing = self.run_on_ui(move |shared| {
+            let (sender, receiver) = oneshot::channel();
+            let mut sender = Some(sender);
+            unsafe {
+                super::browser::Browser::print_to_pdf_pointer(
+                    shared.browser,
+                    path,
+                    &settings,
+                    Some(Box::from(move |ok: bool| {
+                        if let Some(sender) = sender.take() {
+                            let _ = sender.send(ok);
+                        }
+                    })),
+                );
+            }
+            receiver
+        });
+        async move {
+            match printing.await?.await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(Box::from("failed to print to PDF")),
+                Err(_) => Err(Box::from("print was cancelled")),
+            }
+        }
+    }
+
+    /// Print the page to a PDF in memory, see `Browser::print_to_pdf_bytes`
+    pub fn print_to_pdf_bytes(
+        &self,
+        settings: PdfPrintSettings,
+    ) -> impl Future<Output = Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>> + Send {
+        let printing = self.run_on_ui(move |shared| {
+            let (sender, receiver) = oneshot::channel();
+            unsafe {
+                super::browser::Browser::print_to_pdf_bytes_pointer(
+                    shared.browser,
+                    &settings,
+                    Box::from(move |result| {
+                        let _ = sender.send(result);
+                    }),
+                );
+            }
+            receiver
+        });
+        async move {
+            match printing.await?.await {
+                Ok(result) => result.map_err(Box::from),
+                Err(_) => Err(Box::from("print was cancelled")),
+            }
+        }
+    }
+
+    /// Resize the browser window, see `Browser::resize`
+    pub fn resize(&self, width: i32, height: i32) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send {
+        self.run_on_ui(move |shared| unsafe {
+            let host = shared.host();
+            (*host).notify_move_or_resize_started.expect("notify_move_or_resize_started is a function")(host);
+            let window = (*host).get_window_handle.expect("get_window_handle is a function")(host);
+            super::browser::resize_window(window, width, height);
+            (*host).was_resized.expect("was_resized is a function")(host);
+            super::ref_counting::release(host);
+        })
+    }
+
+    /// Close the browser, see `Browser::close`
+    pub fn close(&self, force: bool) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send {
+        let id = self.id();
+        let closing = self.run_on_ui(move |shared| unsafe {
+            let receiver = client::wait_for_close(shared.client, id);
+            if receiver.is_some() {
+                let host = shared.host();
+                (*host).close_browser.expect("close_browser is a function")(host, force as i32);
+                super::ref_counting::release(host);
+            }
+            receiver
+        });
+        async move {
+            match closing.await? {
+                Some(receiver) => receiver.await.map_err(|_| Box::from("browser went away before closing")),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+/// Load `url` in the browser's main frame
+pub unsafe fn navigate(browser: *mut cef_browser_t, url: &str) {
+    let frame = (*browser).get_main_frame.expect("get_main_frame is a function")(browser);
+    if frame.is_null() {
+        return;
+    }
+    let mut cef_url = to_cef_string(url);
+    (*frame).load_url.expect("load_url is a function")(frame, &cef_url);
+    cef_string_utf16_clear(&mut cef_url);
+    super::ref_counting::release(frame);
+}
@@ -0,0 +1,60 @@
+//! The X11 side of browsers: their windows are child X11 windows on CEF's
+//! own display connection
+
+use std::os::raw::{c_int, c_uint, c_ulong, c_void};
+
+use raw_window_handle::{unix::XlibHandle, RawWindowHandle};
+
+use super::super::bindings::{cef_get_xdisplay, cef_string_t, cef_window_handle_t, cef_window_info_t};
+
+#[link(name = "X11")]
+extern "C" {
+    fn XResizeWindow(display: *mut c_void, window: c_ulong, width: c_uint, height: c_uint) -> c_int;
+    fn XFlush(display: *mut c_void) -> c_int;
+}
+
+/// Get the X11 window to create a browser in, from either Xlib or Xcb
+pub(crate) fn parent_window(handle: RawWindowHandle) -> Result<cef_window_handle_t, Box<dyn std::error::Error>> {
+    match handle {
+        RawWindowHandle::Xlib(handle) => Ok(handle.window as cef_window_handle_t),
+        RawWindowHandle::Xcb(handle) => Ok(handle.window as cef_window_handle_t),
+        _ => Err(Box::from("unsupported parent window, expected an Xlib or Xcb window")),
+    }
+}
+
+/// Describe a child window filling `width` x `height` of the parent
+pub(crate) fn window_info(
+    window_name: cef_string_t,
+    parent_window: cef_window_handle_t,
+    width: i32,
+    height: i32,
+) -> cef_window_info_t {
+    cef_window_info_t {
+        window_name,
+        x: 0,
+        y: 0,
+        width: width.max(0) as c_uint,
+        height: height.max(0) as c_uint,
+        parent_window,
+        ..cef_window_info_t::default()
+    }
+}
+
+/// Resize a browser's own window. Must be called on the UI thread
+pub(crate) unsafe fn resize_window(window: cef_window_handle_t, width: i32, height: i32) {
+    let display = cef_get_xdisplay() as *mut c_void;
+    if display.is_null() {
+        log::warn!("no X display to resize the browser window on");
+        return;
+    }
+    XResizeWindow(display, window as c_ulong, width.max(1) as c_uint, height.max(1) as c_uint);
+    XFlush(display);
+}
+
+pub(crate) fn raw_window_handle(window: cef_window_handle_t) -> RawWindowHandle {
+    RawWindowHandle::Xlib(XlibHandle {
+        window: window as c_ulong,
+        display: unsafe { cef_get_xdisplay() } as *mut c_void,
+        ..XlibHandle::empty()
+    })
+}
@@ -5,14 +5,17 @@ use super::schedule::Schedule;
 use std::sync::Arc;
 
 use super::bindings::{
-    cef_base_ref_counted_t, cef_browser_process_handler_t,
+    cef_base_ref_counted_t, cef_browser_process_handler_t, cef_print_handler_t,
 };
+use super::print_backend::PrintBackend;
+use super::print_handler::{self, PrintHandler};
 
 #[repr(C)]
 pub struct BrowserProcessHandler {
     handler: cef_browser_process_handler_t,
     ref_count: AtomicUsize,
     schedule: Arc<Schedule>,
+    print_handler: *mut PrintHandler,
 }
 
 impl BrowserProcessHandler {
@@ -28,6 +31,17 @@ unsafe extern "C" fn on_schedule_message_pump_work(slf: *mut cef_browser_process
     handler.schedule.schedule_work(delay_ms);
 }
 
+unsafe extern "C" fn get_print_handler(slf: *mut cef_browser_process_handler_t) -> *mut cef_print_handler_t {
+    let handler = slf as *mut BrowserProcessHandler;
+    let print_handler = (*handler).print_handler;
+    // without a backend, leave printing to CEF
+    if !print_handler::has_backend(print_handler) {
+        return std::ptr::null_mut();
+    }
+    super::ref_counting::add_ref(print_handler);
+    print_handler as *mut cef_print_handler_t
+}
+
 pub fn allocate(schedule: Arc<Schedule>) -> *mut BrowserProcessHandler {
     let handler = BrowserProcessHandler {
         handler: cef_browser_process_handler_t {
@@ -41,16 +55,22 @@ pub fn allocate(schedule: Arc<Schedule>) -> *mut BrowserProcessHandler {
             on_context_initialized: None,
             on_before_child_process_launch: None,
             on_render_process_thread_created: None,
-            get_print_handler: None,
+            get_print_handler: Some(get_print_handler),
             on_schedule_message_pump_work: Some(on_schedule_message_pump_work),
         },
         ref_count: AtomicUsize::new(1),
         schedule,
+        print_handler: print_handler::allocate(),
     };
 
+    super::ref_counting::allocated("BrowserProcessHandler");
     Box::into_raw(Box::from(handler))
 }
 
+pub unsafe fn set_print_backend(slf: *mut BrowserProcessHandler, backend: Box<dyn PrintBackend + Send>) {
+    print_handler::set_backend((*slf).print_handler, backend);
+}
+
 extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
     let life_span_handler = base as *mut BrowserProcessHandler;
     unsafe {
@@ -71,7 +91,9 @@ extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
 
     if count == 0 {
         unsafe {
-            Box::from_raw(life_span_handler);
+            let handler: Box<BrowserProcessHandler> = Box::from_raw(life_span_handler);
+            super::ref_counting::freed("BrowserProcessHandler");
+            super::ref_counting::release(handler.print_handler);
         }
         1
     } else {
@@ -0,0 +1,156 @@
+use std::sync::Mutex;
+
+use super::bindings::cef_browser_t;
+use super::browser::Browser;
+use super::life_span_handler::BrowserClosed;
+use super::schedule::PumpWaker;
+
+/// Keeps a reference to every browser that is alive, from `on_after_created`
+/// until `on_before_close`, so that they can be looked up and all be closed
+/// before shutting down CEF
+pub struct BrowserRegistry {
+    browsers: Mutex<Vec<BrowserRef>>,
+    created: Mutex<Option<Box<dyn FnMut(&Browser) + Send>>>,
+    destroyed: Mutex<Option<Box<dyn FnMut(&BrowserClosed) + Send>>>,
+    quit_when_empty: Mutex<Option<PumpWaker>>,
+}
+
+/// A reference the registry holds on a browser. CEF browsers are reference
+/// counted thread-safely and the registry only calls `get_identifier`,
+/// `is_same` and `get_host`, which CEF allows on any browser process thread
+struct BrowserRef(*mut cef_browser_t);
+
+unsafe impl Send for BrowserRef {}
+
+impl BrowserRegistry {
+    pub fn new() -> BrowserRegistry {
+        BrowserRegistry {
+            browsers: Mutex::new(Vec::new()),
+            created: Mutex::new(None),
+            destroyed: Mutex::new(None),
+            quit_when_empty: Mutex::new(None),
+        }
+    }
+
+    /// Start tracking a browser, taking a new reference to it
+    pub unsafe fn insert(&self, browser: *mut cef_browser_t) {
+        let mut browsers = self.browsers.lock().expect("browser registry isn't poisoned");
+        if browsers.iter().any(|b| is_same(b.0, browser)) {
+            return;
+        }
+        (*browser).base.add_ref.expect("add_ref is a function")(&mut (*browser).base);
+        browsers.push(BrowserRef(browser));
+    }
+
+    /// Stop tracking a browser, releasing our reference to it
+    pub unsafe fn remove(&self, browser: *mut cef_browser_t) {
+        let removed = {
+            let mut browsers = self.browsers.lock().expect("browser registry isn't poisoned");
+            browsers
+                .iter()
+                .position(|b| is_same(b.0, browser))
+                .map(|i| browsers.remove(i).0)
+        };
+        if let Some(removed) = removed {
+            (*removed).base.release.expect("release is a function")(&mut (*removed).base);
+        }
+
+        if self.is_empty() {
+            if let Some(waker) = &*self.quit_when_empty.lock().expect("browser registry isn't poisoned") {
+                log::debug!("last browser closed, quitting");
+                waker.quit();
+            }
+        }
+    }
+
+    /// Quit `Cef::run` once the last browser has closed
+    pub fn set_quit_when_empty(&self, waker: Option<PumpWaker>) {
+        *self.quit_when_empty.lock().expect("browser registry isn't poisoned") = waker;
+    }
+
+    /// Get a new reference to every tracked browser
+    pub fn browsers(&self) -> Vec<*mut cef_browser_t> {
+        let browsers = self.browsers.lock().expect("browser registry isn't poisoned");
+        browsers
+            .iter()
+            .map(|browser| unsafe {
+                (*browser.0).base.add_ref.expect("add_ref is a function")(&mut (*browser.0).base);
+                browser.0
+            })
+            .collect()
+    }
+
+    /// Get a new reference to the browser with the given id
+    pub fn find(&self, id: i32) -> Option<*mut cef_browser_t> {
+        let browsers = self.browsers.lock().expect("browser registry isn't poisoned");
+        browsers.iter().find(|b| unsafe { browser_id(b.0) } == id).map(|browser| unsafe {
+            (*browser.0).base.add_ref.expect("add_ref is a function")(&mut (*browser.0).base);
+            browser.0
+        })
+    }
+
+    pub fn contains_id(&self, id: i32) -> bool {
+        let browsers = self.browsers.lock().expect("browser registry isn't poisoned");
+        browsers.iter().any(|b| unsafe { browser_id(b.0) } == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.browsers.lock().expect("browser registry isn't poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Ask every browser to close. Closing may call back into the registry,
+    /// so this works on a copy of the list
+    pub unsafe fn close_all(&self, force: bool) {
+        for browser in self.browsers() {
+            let host = (*browser).get_host.expect("get_host is a function")(browser);
+            (*host).close_browser.expect("close_browser is a function")(host, force as i32);
+            (*host).base.release.expect("release is a function")(&mut (*host).base);
+            (*browser).base.release.expect("release is a function")(&mut (*browser).base);
+        }
+    }
+
+    pub fn set_created_listener(&self, listener: Box<dyn FnMut(&Browser) + Send>) {
+        *self.created.lock().expect("browser registry isn't poisoned") = Some(listener);
+    }
+
+    pub fn set_destroyed_listener(&self, listener: Box<dyn FnMut(&BrowserClosed) + Send>) {
+        *self.destroyed.lock().expect("browser registry isn't poisoned") = Some(listener);
+    }
+
+    pub unsafe fn notify_created(&self, browser: *mut cef_browser_t) {
+        // take the listener out while calling it, so that it can use the
+        // registry itself
+        let listener = self.created.lock().expect("browser registry isn't poisoned").take();
+        if let Some(mut listener) = listener {
+            (*browser).base.add_ref.expect("add_ref is a function")(&mut (*browser).base);
+            listener(&Browser::from_raw(browser));
+            let mut slot = self.created.lock().expect("browser registry isn't poisoned");
+            if slot.is_none() {
+                *slot = Some(listener);
+            }
+        }
+    }
+
+    pub fn notify_destroyed(&self, event: &BrowserClosed) {
+        let listener = self.destroyed.lock().expect("browser registry isn't poisoned").take();
+        if let Some(mut listener) = listener {
+            listener(event);
+            let mut slot = self.destroyed.lock().expect("browser registry isn't poisoned");
+            if slot.is_none() {
+                *slot = Some(listener);
+            }
+        }
+    }
+}
+
+pub unsafe fn browser_id(browser: *mut cef_browser_t) -> i32 {
+    (*browser).get_identifier.expect("get_identifier is a function")(browser)
+}
+
+pub unsafe fn is_same(a: *mut cef_browser_t, b: *mut cef_browser_t) -> bool {
+    (*a).is_same.expect("is_same is a function")(a, b) == 1
+}
@@ -0,0 +1,236 @@
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+
+use super::bindings::{cef_request_callback_t, cef_sslinfo_t, cef_x509cert_principal_t};
+use super::value::{from_cef_time, from_cef_userfree};
+
+/// The parts of a server's certificate that matter when deciding whether to
+/// trust it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub valid_start: Option<SystemTime>,
+    pub valid_expiry: Option<SystemTime>,
+    /// The DER encoding of the certificate
+    pub der: Vec<u8>,
+    /// The SHA-256 hash of the DER encoding
+    pub sha256_fingerprint: [u8; 32],
+}
+
+/// A certificate error that occurred while loading a URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateError {
+    /// The `cef_errorcode_t` of the error, i.e. `ERR_CERT_AUTHORITY_INVALID`
+    pub code: i32,
+    pub url: String,
+    pub certificate: Option<CertificateInfo>,
+}
+
+/// The answer to a certificate error. Dropping the decision without answering
+/// denies the connection
+pub struct CertificateDecision {
+    callback: *mut cef_request_callback_t,
+}
+
+impl CertificateDecision {
+    /// Continue loading despite the error
+    pub fn allow(mut self) {
+        self.respond(true);
+    }
+
+    /// Refuse the connection
+    pub fn deny(mut self) {
+        self.respond(false);
+    }
+
+    fn respond(&mut self, allow: bool) {
+        if self.callback.is_null() {
+            return;
+        }
+        unsafe {
+            (*self.callback).cont.expect("cont is a function")(self.callback, allow as i32);
+            (*self.callback).base.release.expect("release is a function")(&mut (*self.callback).base);
+        }
+        self.callback = std::ptr::null_mut();
+    }
+}
+
+impl Drop for CertificateDecision {
+    fn drop(&mut self) {
+        self.respond(false);
+    }
+}
+
+/// Decides whether to continue loading pages with certificate errors, i.e.
+/// self-signed certificates on local devices. Certificates with a pinned
+/// fingerprint are allowed; everything else is denied unless an
+/// `on_unpinned` callback decides otherwise
+#[derive(Default)]
+pub struct CertificatePolicy {
+    pinned: Vec<[u8; 32]>,
+    on_unpinned: Option<Box<dyn FnMut(CertificateError, CertificateDecision)>>,
+}
+
+impl CertificatePolicy {
+    pub fn new() -> CertificatePolicy {
+        CertificatePolicy::default()
+    }
+
+    /// Trust certificates with this SHA-256 fingerprint
+    pub fn pin(mut self, sha256_fingerprint: [u8; 32]) -> CertificatePolicy {
+        self.pinned.push(sha256_fingerprint);
+        self
+    }
+
+    /// Trust certificates with this SHA-256 fingerprint, written in hex with
+    /// or without colons, i.e. `AB:CD:...`
+    pub fn pin_hex(self, sha256_fingerprint: &str) -> Result<CertificatePolicy, Box<dyn std::error::Error>> {
+        let hex: Vec<char> = sha256_fingerprint.chars().filter(|c| *c != ':').collect();
+        // `from_str_radix` would also take a sign, so check the digits first
+        if hex.len() != 64 || !hex.iter().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Box::from(format!("`{}` isn't a SHA-256 fingerprint", sha256_fingerprint)));
+        }
+        let mut fingerprint = [0u8; 32];
+        for (i, pair) in hex.chunks(2).enumerate() {
+            let pair: String = pair.iter().collect();
+            fingerprint[i] = u8::from_str_radix(&pair, 16)?;
+        }
+        Ok(self.pin(fingerprint))
+    }
+
+    /// Decide about certificates that aren't pinned. The decision can be
+    /// made asynchronously, but must be made on the UI thread
+    pub fn on_unpinned<F: FnMut(CertificateError, CertificateDecision) + 'static>(
+        mut self,
+        callback: F,
+    ) -> CertificatePolicy {
+        self.on_unpinned = Some(Box::from(callback));
+        self
+    }
+
+    fn decide(&mut self, error: CertificateError, decision: CertificateDecision) {
+        let pinned = match &error.certificate {
+            Some(certificate) => self.pinned.contains(&certificate.sha256_fingerprint),
+            None => false,
+        };
+        if pinned {
+            log::debug!("allowing pinned certificate for `{}`", error.url);
+            decision.allow();
+        } else if let Some(on_unpinned) = &mut self.on_unpinned {
+            on_unpinned(error, decision);
+        } else {
+            log::warn!("denying certificate error {} for `{}`", error.code, error.url);
+            decision.deny();
+        }
+    }
+}
+
+unsafe fn principal_name(principal: *mut cef_x509cert_principal_t) -> String {
+    if principal.is_null() {
+        return String::new();
+    }
+    let name = from_cef_userfree((*principal).get_display_name.expect("get_display_name is a function")(principal));
+    (*principal).base.release.expect("release is a function")(&mut (*principal).base);
+    name
+}
+
+unsafe fn read_certificate(ssl_info: *mut cef_sslinfo_t) -> Option<CertificateInfo> {
+    if ssl_info.is_null() {
+        return None;
+    }
+    let certificate = (*ssl_info).get_x509certificate.expect("get_x509certificate is a function")(ssl_info);
+    if certificate.is_null() {
+        return None;
+    }
+
+    let der = (*certificate).get_derencoded.expect("get_derencoded is a function")(certificate);
+    let der: Vec<u8> = if der.is_null() {
+        Vec::new()
+    } else {
+        let size = (*der).get_size.expect("get_size is a function")(der);
+        let mut bytes: Vec<u8> = vec![0; size as usize];
+        (*der).get_data.expect("get_data is a function")(der, bytes.as_mut_ptr() as *mut std::os::raw::c_void, size, 0);
+        (*der).base.release.expect("release is a function")(&mut (*der).base);
+        bytes
+    };
+    let mut sha256_fingerprint = [0u8; 32];
+    sha256_fingerprint.copy_from_slice(&Sha256::digest(&der));
+
+    let info = CertificateInfo {
+        subject: principal_name((*certificate).get_subject.expect("get_subject is a function")(certificate)),
+        issuer: principal_name((*certificate).get_issuer.expect("get_issuer is a function")(certificate)),
+        valid_start: from_cef_time(&(*certificate).get_valid_start.expect("get_valid_start is a function")(certificate)),
+        valid_expiry: from_cef_time(&(*certificate).get_valid_expiry.expect("get_valid_expiry is a function")(certificate)),
+        der,
+        sha256_fingerprint,
+    };
+    (*certificate).base.release.expect("release is a function")(&mut (*certificate).base);
+    Some(info)
+}
+
+/// Run a certificate error through the policy, the callback is always
+/// answered eventually
+pub unsafe fn handle_certificate_error(
+    policy: &mut CertificatePolicy,
+    code: i32,
+    url: String,
+    ssl_info: *mut cef_sslinfo_t,
+    callback: *mut cef_request_callback_t,
+) {
+    let error = CertificateError {
+        code,
+        url,
+        certificate: read_certificate(ssl_info),
+    };
+    (*callback).base.add_ref.expect("add_ref is a function")(&mut (*callback).base);
+    policy.decide(error, CertificateDecision { callback });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CertificatePolicy;
+
+    const FINGERPRINT: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    fn expected() -> [u8; 32] {
+        let mut fingerprint = [0u8; 32];
+        for (i, byte) in fingerprint.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        fingerprint
+    }
+
+    #[test]
+    fn pin_hex_parses_plain_hex() {
+        let policy = CertificatePolicy::new().pin_hex(FINGERPRINT).unwrap();
+        assert_eq!(policy.pinned, vec![expected()]);
+    }
+
+    #[test]
+    fn pin_hex_parses_colons_and_uppercase() {
+        let colons: Vec<String> = FINGERPRINT
+            .to_uppercase()
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| String::from_utf8(pair.to_vec()).unwrap())
+            .collect();
+        let policy = CertificatePolicy::new().pin_hex(&colons.join(":")).unwrap();
+        assert_eq!(policy.pinned, vec![expected()]);
+    }
+
+    #[test]
+    fn pin_hex_rejects_wrong_length() {
+        assert!(CertificatePolicy::new().pin_hex(&FINGERPRINT[2..]).is_err());
+        assert!(CertificatePolicy::new().pin_hex(&format!("{}00", FINGERPRINT)).is_err());
+        assert!(CertificatePolicy::new().pin_hex("").is_err());
+    }
+
+    #[test]
+    fn pin_hex_rejects_signs_and_non_hex() {
+        assert!(CertificatePolicy::new().pin_hex(&format!("+1{}", &FINGERPRINT[2..])).is_err());
+        assert!(CertificatePolicy::new().pin_hex(&format!("-1{}", &FINGERPRINT[2..])).is_err());
+        assert!(CertificatePolicy::new().pin_hex(&format!("zz{}", &FINGERPRINT[2..])).is_err());
+    }
+}
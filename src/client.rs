@@ -1,16 +1,27 @@
 use std::mem::size_of;
 use std::os::raw::c_int;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use super::bindings::{
-    cef_base_ref_counted_t, cef_browser_t, cef_client_t, cef_context_menu_handler_t,
-    cef_display_handler_t, cef_frame_t, cef_life_span_handler_t, cef_process_id_t,
-    cef_process_message_t, cef_request_handler_t, cef_string_t, cef_string_userfree_t,
-    cef_string_userfree_utf16_free,
+    cef_base_ref_counted_t, cef_browser_t, cef_client_t, cef_context_menu_handler_t, cef_dialog_handler_t,
+    cef_display_handler_t, cef_drag_handler_t, cef_focus_handler_t, cef_frame_t, cef_life_span_handler_t, cef_process_id_t,
+    cef_list_value_t, cef_process_message_t, cef_request_handler_t, cef_string_t, cef_string_userfree_t,
+    cef_string_userfree_utf16_free, cef_string_utf16_clear,
 };
+use super::browser_registry::BrowserRegistry;
 use super::context_menu_handler::{self, ContextMenuHandler};
+use super::dev_tools::{self, DevToolsObserver};
+use super::dialog_handler::{self, DialogHandler};
 use super::display_handler::{self, DisplayHandler};
-use super::life_span_handler::{self, LifeSpanHandler};
+use super::drag_handler::{self, DragEnter, DragHandler, DragPolicy};
+use super::file_dialog::{FileDialogHandler, FileDialogOptions};
+use super::focus_handler::{self, FocusHandler, FocusSource};
+use super::life_span_handler::{self, BrowserClosed, LifeSpanHandler};
+use super::pdf_print_settings::PdfPrintSettings;
+use super::certificate_policy::CertificatePolicy;
+use super::crash_recovery::{CrashRecovery, RenderProcessTerminated};
+use super::credential_provider::CredentialProvider;
 use super::request_handler::{self, RequestHandler};
 
 #[repr(C)]
@@ -21,6 +32,10 @@ pub struct Client {
     context_menu_handler: *mut ContextMenuHandler,
     request_handler: *mut RequestHandler,
     display_handler: *mut DisplayHandler,
+    dev_tools_observer: *mut DevToolsObserver,
+    focus_handler: *mut FocusHandler,
+    drag_handler: *mut DragHandler,
+    dialog_handler: *mut DialogHandler,
 }
 
 extern "C" fn get_life_span_handler(slf: *mut cef_client_t) -> *mut cef_life_span_handler_t {
@@ -51,6 +66,58 @@ extern "C" fn get_display_handler(slf: *mut cef_client_t) -> *mut cef_display_ha
     handler as *mut cef_display_handler_t
 }
 
+extern "C" fn get_focus_handler(slf: *mut cef_client_t) -> *mut cef_focus_handler_t {
+    let client = slf as *mut Client;
+    let handler = unsafe { (*client).focus_handler };
+    unsafe { (*handler).inc_ref() };
+    handler as *mut cef_focus_handler_t
+}
+
+extern "C" fn get_drag_handler(slf: *mut cef_client_t) -> *mut cef_drag_handler_t {
+    let client = slf as *mut Client;
+    let handler = unsafe { (*client).drag_handler };
+    unsafe { (*handler).inc_ref() };
+    handler as *mut cef_drag_handler_t
+}
+
+extern "C" fn get_dialog_handler(slf: *mut cef_client_t) -> *mut cef_dialog_handler_t {
+    let client = slf as *mut Client;
+    let handler = unsafe { (*client).dialog_handler };
+    unsafe { (*handler).inc_ref() };
+    handler as *mut cef_dialog_handler_t
+}
+
+/// Holds a reference to a frame for as long as a reply closure needs it
+struct FrameRef(*mut cef_frame_t);
+
+impl FrameRef {
+    unsafe fn new(frame: *mut cef_frame_t) -> FrameRef {
+        super::ref_counting::add_ref(frame);
+        FrameRef(frame)
+    }
+}
+
+impl Drop for FrameRef {
+    fn drop(&mut self) {
+        unsafe { super::ref_counting::release(self.0) };
+    }
+}
+
+/// Read the JSON encoded PDF print options at `index` in a process message's
+/// arguments, falling back to the defaults if there are none
+unsafe fn pdf_print_settings_arg(args: *mut cef_list_value_t, index: usize) -> Result<PdfPrintSettings, String> {
+    let num_args = (*args).get_size.expect("get_size is a function")(args) as usize;
+    if num_args <= index {
+        return Ok(PdfPrintSettings::default());
+    }
+
+    let options = ((*args).get_string.expect("get_string is a function"))(args, index as _);
+    let options = super::value::from_cef_userfree(options);
+    serde_json::from_str(&options)
+        .map_err(|e| e.to_string())
+        .and_then(|options| PdfPrintSettings::from_json(&options))
+}
+
 unsafe extern "C" fn on_process_message_received(
     _slf: *mut cef_client_t,
     browser: *mut cef_browser_t,
@@ -84,155 +151,138 @@ unsafe extern "C" fn on_process_message_received(
             .collect::<String>();
         cef_string_userfree_utf16_free(cef_path);
 
-        super::browser::Browser::print_to_pdf_pointer(
-            browser,
-            path,
-            Some(Box::from(move |ok| {
-                // now send an IPC message back to the renderer
-                // convert the message name to a CEF string
-                let mut cef_message_name = cef_string_t::default();
-                let message_name = "print_to_pdf_done".as_bytes();
-                let message_name = std::ffi::CString::new(message_name).unwrap();
-                super::bindings::cef_string_utf8_to_utf16(
-                    message_name.as_ptr(),
-                    message_name.to_bytes().len() as u64,
-                    &mut cef_message_name,
-                );
-
-                // build the message
-                let message = super::bindings::cef_process_message_create(&cef_message_name);
-                let args = ((*message)
-                    .get_argument_list
-                    .expect("get_argument_list is a function"))(message);
-                ((*args).set_size.expect("set_size is a function"))(args, 1);
-                ((*args).set_bool.expect("set_bool is a function"))(args, 0, ok as i32);
-
-                // send the message
-                ((*frame)
-                    .send_process_message
-                    .expect("send_process_message is a function"))(
-                    frame,
-                    super::bindings::cef_process_id_t_PID_RENDERER,
-                    message,
-                );
-            })),
-        );
+        // get the options, if the renderer sent any
+        let settings = pdf_print_settings_arg(args, 1);
+
+        // keep the frame alive until we can reply to it
+        let frame = FrameRef::new(frame);
+        let mut on_done: Box<dyn FnMut(bool)> = Box::from(move |ok| {
+            // now send an IPC message back to the renderer
+            // convert the message name to a CEF string
+            let mut cef_message_name = cef_string_t::default();
+            let message_name = "print_to_pdf_done".as_bytes();
+            let message_name = std::ffi::CString::new(message_name).unwrap();
+            super::bindings::cef_string_utf8_to_utf16(
+                message_name.as_ptr(),
+                message_name.to_bytes().len() as u64,
+                &mut cef_message_name,
+            );
+
+            // build the message
+            let message = super::bindings::cef_process_message_create(&cef_message_name);
+            let args = ((*message)
+                .get_argument_list
+                .expect("get_argument_list is a function"))(message);
+            ((*args).set_size.expect("set_size is a function"))(args, 1);
+            ((*args).set_bool.expect("set_bool is a function"))(args, 0, ok as i32);
+
+            // send the message
+            ((*frame.0)
+                .send_process_message
+                .expect("send_process_message is a function"))(
+                frame.0,
+                super::bindings::cef_process_id_t_PID_RENDERER,
+                message,
+            );
+        });
+        match settings {
+            Ok(settings) => {
+                super::browser::Browser::print_to_pdf_pointer(browser, path, &settings, Some(on_done))
+            }
+            Err(e) => {
+                log::warn!("invalid PDF print options: {}", e);
+                on_done(false);
+            }
+        }
 
         1
-    } else if message_name == "save_file_dialog" || message_name == "open_file_dialog" {
+    } else if message_name == "print_to_pdf_data" {
         let args = ((*message)
             .get_argument_list
             .expect("get_argument_list is a function"))(message);
+        let settings = pdf_print_settings_arg(args, 0);
 
-        let num_args = (*args).get_size.expect("get_size is a function")(args);
-        debug_assert_eq!(num_args, 3);
+        // keep the frame alive until we can reply to it
+        let frame = FrameRef::new(frame);
+        let on_done = move |result: Result<Vec<u8>, String>| {
+            // reply with whether it worked and either the base64 encoded PDF
+            // or the error
+            let mut cef_message_name = super::value::to_cef_string("print_to_pdf_data_done");
+            let message = super::bindings::cef_process_message_create(&cef_message_name);
+            cef_string_utf16_clear(&mut cef_message_name);
+            let args = ((*message)
+                .get_argument_list
+                .expect("get_argument_list is a function"))(message);
+            let (ok, payload) = match result {
+                Ok(bytes) => (true, base64::encode(&bytes)),
+                Err(e) => (false, e),
+            };
+            let mut cef_payload = super::value::to_cef_string(&payload);
+            ((*args).set_size.expect("set_size is a function"))(args, 2);
+            ((*args).set_bool.expect("set_bool is a function"))(args, 0, ok as i32);
+            ((*args).set_string.expect("set_string is a function"))(args, 1, &cef_payload);
+            cef_string_utf16_clear(&mut cef_payload);
 
-        // get the title
-        let cef_title: cef_string_userfree_t =
-            ((*args).get_string.expect("get_string is a function"))(args, 0);
-        let title: String = if cef_title == std::ptr::null_mut() {
-            let chars: *mut u16 = (*cef_title).str;
-            let len: usize = (*cef_title).length as usize;
-            let chars = std::slice::from_raw_parts(chars, len);
-            let title = std::char::decode_utf16(chars.iter().cloned())
-                .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
-                .collect::<String>();
-            cef_string_userfree_utf16_free(cef_title);
-            title
-        }
-        else {
-            "".to_owned()
+            ((*frame.0)
+                .send_process_message
+                .expect("send_process_message is a function"))(
+                frame.0,
+                super::bindings::cef_process_id_t_PID_RENDERER,
+                message,
+            );
         };
-
-        // get the initial_file_name
-        let cef_initial_file_name: cef_string_userfree_t =
-            ((*args).get_string.expect("get_string is a function"))(args, 1);
-        let initial_file_name: String = if cef_initial_file_name == std::ptr::null_mut() {
-            "".to_owned()
+        match settings {
+            Ok(settings) => {
+                super::browser::Browser::print_to_pdf_bytes_pointer(browser, &settings, Box::from(on_done))
+            }
+            Err(e) => {
+                log::warn!("invalid PDF print options: {}", e);
+                on_done(Err(e));
+            }
         }
-        else {
-            let chars: *mut u16 = (*cef_initial_file_name).str;
-            let len: usize = (*cef_initial_file_name).length as usize;
-            let chars = std::slice::from_raw_parts(chars, len);
-            let initial_file_name = std::char::decode_utf16(chars.iter().cloned())
-                .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
-                .collect::<String>();
-            cef_string_userfree_utf16_free(cef_initial_file_name);
-            initial_file_name
-        };
 
-        // get the filter
-        let cef_filter: cef_string_userfree_t =
-            ((*args).get_string.expect("get_string is a function"))(args, 2);
-        let filter: String = if cef_filter == std::ptr::null_mut() {
-            let chars: *mut u16 = (*cef_filter).str;
-            let len: usize = (*cef_filter).length as usize;
-            let chars = std::slice::from_raw_parts(chars, len);
-            let filter = std::char::decode_utf16(chars.iter().cloned())
-                .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
-                .collect::<String>();
-            cef_string_userfree_utf16_free(cef_filter);
-            filter
-        }
-        else {
-            "".to_owned()
-        };
+        1
+    } else if message_name == "run_file_dialog" {
+        let args = ((*message)
+            .get_argument_list
+            .expect("get_argument_list is a function"))(message);
+        let options = ((*args).get_string.expect("get_string is a function"))(args, 0);
+        let options = super::value::from_cef_userfree(options);
+        let options = serde_json::from_str(&options)
+            .map_err(|e| e.to_string())
+            .and_then(|options| FileDialogOptions::from_json(&options));
 
-        super::browser::Browser::run_file_dialog_pointer(
-            browser,
-            match message_name.as_ref() {
-                "open_file_dialog" => super::v8_file_dialog_handler::FileDialogMode::Open,
-                "save_file_dialog" => super::v8_file_dialog_handler::FileDialogMode::Save,
-                _ => unreachable!()
-            },
-            title,
-            initial_file_name,
-            filter,
-            Some(Box::from(move |path: Option<std::path::PathBuf>| {
-                log::debug!("client save callback");
-                // now send an IPC message back to the renderer
-                // convert the message name to a CEF string
-                let mut cef_message_name = cef_string_t::default();
-                let message_name = "run_file_dialog_done".as_bytes();
-                let message_name = std::ffi::CString::new(message_name).unwrap();
-                super::bindings::cef_string_utf8_to_utf16(
-                    message_name.as_ptr(),
-                    message_name.to_bytes().len() as u64,
-                    &mut cef_message_name,
-                );
-
-                // build the message
-                let message = super::bindings::cef_process_message_create(&cef_message_name);
-                let args = ((*message)
-                    .get_argument_list
-                    .expect("get_argument_list is a function"))(message);
-                if let Some(path) = path {
-                    ((*args).set_size.expect("set_size is a function"))(args, 1);
-
-                    let mut cef_path = cef_string_t::default();
-                    let path = path.display().to_string();
-                    let path = path.as_bytes();
-                    let path = std::ffi::CString::new(path).unwrap();
-                    super::bindings::cef_string_utf8_to_utf16(
-                        path.as_ptr(),
-                        path.to_bytes().len() as u64,
-                        &mut cef_path,
-                    );
-                    ((*args).set_string.expect("set_string is a function"))(args, 0, &cef_path);
-                } else {
-                    ((*args).set_size.expect("set_size is a function"))(args, 0);
-                }
-
-                // and finally send the message
-                ((*frame)
-                    .send_process_message
-                    .expect("send_process_message is a function"))(
-                    frame,
-                    super::bindings::cef_process_id_t_PID_RENDERER,
-                    message,
-                );
-            })),
-        );
+        let frame = FrameRef::new(frame);
+        let mut on_done: Box<dyn FnMut(Vec<std::path::PathBuf>)> = Box::from(move |paths: Vec<std::path::PathBuf>| {
+            // now send an IPC message back to the renderer, with the paths
+            // as a JSON array
+            let paths: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+            let mut cef_message_name = super::value::to_cef_string("run_file_dialog_done");
+            let message = super::bindings::cef_process_message_create(&cef_message_name);
+            cef_string_utf16_clear(&mut cef_message_name);
+            let args = ((*message)
+                .get_argument_list
+                .expect("get_argument_list is a function"))(message);
+            let mut cef_paths = super::value::to_cef_string(&serde_json::to_string(&paths).unwrap());
+            ((*args).set_size.expect("set_size is a function"))(args, 1);
+            ((*args).set_string.expect("set_string is a function"))(args, 0, &cef_paths);
+            cef_string_utf16_clear(&mut cef_paths);
+
+            ((*frame.0)
+                .send_process_message
+                .expect("send_process_message is a function"))(
+                frame.0,
+                super::bindings::cef_process_id_t_PID_RENDERER,
+                message,
+            );
+        });
+        match options {
+            Ok(options) => super::browser::Browser::run_file_dialog_pointer(browser, &options, Some(on_done)),
+            Err(e) => {
+                log::warn!("invalid file dialog options: {}", e);
+                on_done(Vec::new());
+            }
+        }
 
         1
     } else {
@@ -240,7 +290,7 @@ unsafe extern "C" fn on_process_message_received(
     }
 }
 
-pub fn allocate() -> *mut Client {
+pub fn allocate(registry: Arc<BrowserRegistry>) -> *mut Client {
     let client = Client {
         client: cef_client_t {
             base: cef_base_ref_counted_t {
@@ -251,12 +301,12 @@ pub fn allocate() -> *mut Client {
                 has_at_least_one_ref: Some(has_at_least_one_ref),
             },
             get_context_menu_handler: Some(get_context_menu_handler),
-            get_dialog_handler: None,
+            get_dialog_handler: Some(get_dialog_handler),
             get_display_handler: Some(get_display_handler),
             get_download_handler: None,
-            get_drag_handler: None,
+            get_drag_handler: Some(get_drag_handler),
             get_find_handler: None,
-            get_focus_handler: None,
+            get_focus_handler: Some(get_focus_handler),
             get_jsdialog_handler: None,
             get_keyboard_handler: None,
             get_life_span_handler: Some(get_life_span_handler),
@@ -266,12 +316,17 @@ pub fn allocate() -> *mut Client {
             on_process_message_received: Some(on_process_message_received),
         },
         ref_count: AtomicUsize::new(1),
-        life_span_handler: life_span_handler::allocate(),
+        life_span_handler: life_span_handler::allocate(registry),
         context_menu_handler: context_menu_handler::allocate(),
         request_handler: request_handler::allocate(),
         display_handler: display_handler::allocate(),
+        dev_tools_observer: dev_tools::allocate(),
+        focus_handler: focus_handler::allocate(),
+        drag_handler: drag_handler::allocate(),
+        dialog_handler: dialog_handler::allocate(),
     };
 
+    super::ref_counting::allocated("Client");
     Box::into_raw(Box::from(client))
 }
 
@@ -280,6 +335,110 @@ pub unsafe fn set_fullscreen_listener<F: FnMut(bool) + 'static>(slf: *mut Client
     super::display_handler::set_fullscreen_listener((*client).display_handler, listener);
 }
 
+pub unsafe fn set_take_focus_listener<F: FnMut(bool) + 'static>(slf: *mut Client, listener: F) {
+    focus_handler::set_take_focus_listener((*slf).focus_handler, listener);
+}
+
+pub unsafe fn set_focus_requested_listener<F: FnMut(FocusSource) -> bool + 'static>(slf: *mut Client, listener: F) {
+    focus_handler::set_focus_requested_listener((*slf).focus_handler, listener);
+}
+
+pub unsafe fn set_got_focus_listener<F: FnMut() + 'static>(slf: *mut Client, listener: F) {
+    focus_handler::set_got_focus_listener((*slf).focus_handler, listener);
+}
+
+pub unsafe fn set_drag_policy(slf: *mut Client, policy: DragPolicy) {
+    drag_handler::set_policy((*slf).drag_handler, policy);
+}
+
+pub unsafe fn set_drag_enter_listener<F: FnMut(&DragEnter) -> bool + 'static>(slf: *mut Client, listener: F) {
+    drag_handler::set_drag_enter_listener((*slf).drag_handler, listener);
+}
+
+pub unsafe fn set_certificate_policy(slf: *mut Client, policy: CertificatePolicy) {
+    request_handler::set_certificate_policy((*slf).request_handler, policy);
+}
+
+pub unsafe fn set_credential_provider<P: CredentialProvider + 'static>(slf: *mut Client, provider: P) {
+    request_handler::set_credential_provider((*slf).request_handler, Box::from(provider));
+}
+
+pub unsafe fn set_file_dialog_handler<H: FileDialogHandler + 'static>(slf: *mut Client, handler: H) {
+    dialog_handler::set_file_dialog_handler((*slf).dialog_handler, Box::from(handler));
+}
+
+pub unsafe fn set_render_process_terminated_listener<F: FnMut(&RenderProcessTerminated) + 'static>(slf: *mut Client, listener: F) {
+    request_handler::set_render_process_terminated_listener((*slf).request_handler, listener);
+}
+
+pub unsafe fn set_crash_recovery(slf: *mut Client, crash_recovery: CrashRecovery) {
+    request_handler::set_crash_recovery((*slf).request_handler, crash_recovery);
+}
+
+pub unsafe fn wait_for_close(slf: *mut Client, browser_id: i32) -> Option<futures::channel::oneshot::Receiver<()>> {
+    life_span_handler::wait_for_close((*slf).life_span_handler, browser_id)
+}
+
+pub unsafe fn is_closing(slf: *mut Client, browser_id: i32) -> bool {
+    life_span_handler::is_closing((*slf).life_span_handler, browser_id)
+}
+
+pub unsafe fn set_closed_listener<F: FnMut(&BrowserClosed) + 'static>(slf: *mut Client, listener: F) {
+    life_span_handler::set_closed_listener((*slf).life_span_handler, listener);
+}
+
+/// Keeps a client and its browser host alive for as long as a conversation
+/// with DevTools spans several awaits
+pub struct DevToolsTarget {
+    client: *mut Client,
+    host: *mut super::bindings::cef_browser_host_t,
+}
+
+impl DevToolsTarget {
+    pub unsafe fn new(client: *mut Client, host: *mut super::bindings::cef_browser_host_t) -> DevToolsTarget {
+        super::ref_counting::add_ref(client);
+        super::ref_counting::add_ref(host);
+        DevToolsTarget { client, host }
+    }
+
+    /// Execute a DevTools method, see `execute_dev_tools_method`
+    pub fn execute(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> impl std::future::Future<Output = Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>>> {
+        unsafe { execute_dev_tools_method(self.client, self.host, method, params) }
+    }
+}
+
+impl Drop for DevToolsTarget {
+    fn drop(&mut self) {
+        unsafe {
+            super::ref_counting::release(self.host);
+            super::ref_counting::release(self.client);
+        }
+    }
+}
+
+/// Execute a DevTools protocol method on the browser this client belongs to.
+/// The method is sent immediately, so this must be called on the UI thread
+pub unsafe fn execute_dev_tools_method(
+    slf: *mut Client,
+    host: *mut super::bindings::cef_browser_host_t,
+    method: &str,
+    params: serde_json::Value,
+) -> impl std::future::Future<Output = Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>>> {
+    let receiver = dev_tools::execute_method((*slf).dev_tools_observer, host, method, &params);
+    let method = method.to_owned();
+    async move {
+        match receiver.await {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(e)) => Err(Box::from(format!("dev tools method `{}` failed: {}", method, e))),
+            Err(_) => Err(Box::from(format!("dev tools method `{}` was cancelled", method))),
+        }
+    }
+}
+
 extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
     let client = base as *mut Client;
     unsafe {
@@ -293,8 +452,19 @@ extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
 
     if count == 0 {
         unsafe {
-            Box::from_raw(client);
-            // TODO: free our handlers here too?
+            let client: Box<Client> = Box::from_raw(client);
+            super::ref_counting::freed("Client");
+            // the dev tools registration holds a reference to the observer, so
+            // drop it first to break the cycle
+            dev_tools::unregister(client.dev_tools_observer);
+            super::ref_counting::release(client.life_span_handler);
+            super::ref_counting::release(client.context_menu_handler);
+            super::ref_counting::release(client.request_handler);
+            super::ref_counting::release(client.display_handler);
+            super::ref_counting::release(client.dev_tools_observer);
+            super::ref_counting::release(client.focus_handler);
+            super::ref_counting::release(client.drag_handler);
+            super::ref_counting::release(client.dialog_handler);
         }
         1
     } else {
@@ -50,6 +50,7 @@ pub fn allocate() -> *mut ContextMenuHandler {
         ref_count: AtomicUsize::new(1),
     };
 
+    super::ref_counting::allocated("ContextMenuHandler");
     Box::into_raw(Box::from(handler))
 }
 
@@ -74,6 +75,7 @@ extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
     if count == 0 {
         unsafe {
             Box::from_raw(context_menu_handler);
+            super::ref_counting::freed("ContextMenuHandler");
         }
         1
     } else {
@@ -0,0 +1,307 @@
+use std::future::Future;
+use std::ptr::null_mut;
+use std::time::SystemTime;
+
+use futures::channel::oneshot;
+
+use super::bindings::{
+    cef_completion_callback_t, cef_cookie_manager_get_global_manager, cef_cookie_manager_t,
+    cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_LAX_MODE,
+    cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_NO_RESTRICTION,
+    cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_STRICT_MODE,
+    cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_UNSPECIFIED, cef_cookie_t,
+    cef_cookie_visitor_t, cef_delete_cookies_callback_t, cef_set_cookie_callback_t,
+    cef_string_utf16_clear,
+};
+use super::completion_callback;
+use super::cookie_visitor;
+use super::delete_cookies_callback;
+use super::set_cookie_callback;
+use super::value::{from_cef_string, from_cef_time, to_cef_string, to_cef_time};
+
+/// The `SameSite` attribute of a cookie
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Unspecified,
+    NoRestriction,
+    Lax,
+    Strict,
+}
+
+impl Default for SameSite {
+    fn default() -> SameSite {
+        SameSite::Unspecified
+    }
+}
+
+/// A single cookie
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// Leave empty to create a host cookie for the URL it is set on
+    pub domain: String,
+    pub path: String,
+    pub secure: bool,
+    pub httponly: bool,
+    /// `None` for a session cookie
+    pub expires: Option<SystemTime>,
+    pub same_site: SameSite,
+}
+
+impl Cookie {
+    pub(crate) unsafe fn from_cef(cookie: &cef_cookie_t) -> Cookie {
+        #[allow(non_upper_case_globals)]
+        let same_site = match cookie.same_site {
+            cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_NO_RESTRICTION => SameSite::NoRestriction,
+            cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_LAX_MODE => SameSite::Lax,
+            cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_STRICT_MODE => SameSite::Strict,
+            _ => SameSite::Unspecified,
+        };
+        Cookie {
+            name: from_cef_string(&cookie.name),
+            value: from_cef_string(&cookie.value),
+            domain: from_cef_string(&cookie.domain),
+            path: from_cef_string(&cookie.path),
+            secure: cookie.secure == 1,
+            httponly: cookie.httponly == 1,
+            expires: if cookie.has_expires == 1 {
+                from_cef_time(&cookie.expires)
+            } else {
+                None
+            },
+            same_site,
+        }
+    }
+
+    /// Convert to a CEF cookie, the strings in which must be cleared afterwards
+    unsafe fn to_cef(&self) -> cef_cookie_t {
+        let mut cookie = cef_cookie_t::default();
+        cookie.name = to_cef_string(&self.name);
+        cookie.value = to_cef_string(&self.value);
+        cookie.domain = to_cef_string(&self.domain);
+        cookie.path = to_cef_string(&self.path);
+        cookie.secure = self.secure as i32;
+        cookie.httponly = self.httponly as i32;
+        if let Some(expires) = self.expires {
+            cookie.has_expires = 1;
+            cookie.expires = to_cef_time(expires);
+        }
+        cookie.same_site = match self.same_site {
+            SameSite::Unspecified => cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_UNSPECIFIED,
+            SameSite::NoRestriction => cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_NO_RESTRICTION,
+            SameSite::Lax => cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_LAX_MODE,
+            SameSite::Strict => cef_cookie_same_site_t_CEF_COOKIE_SAME_SITE_STRICT_MODE,
+        };
+        cookie
+    }
+}
+
+/// Access to the cookies of a request context
+pub struct CookieManager {
+    manager: *mut cef_cookie_manager_t,
+}
+
+impl CookieManager {
+    /// Take ownership of a cookie manager reference handed to us by CEF
+    pub(crate) unsafe fn from_raw(manager: *mut cef_cookie_manager_t) -> Option<CookieManager> {
+        if manager.is_null() {
+            None
+        } else {
+            Some(CookieManager { manager })
+        }
+    }
+
+    /// The cookie manager of the global request context, `None` if CEF hasn't
+    /// been initialized yet
+    pub fn global() -> Option<CookieManager> {
+        unsafe { CookieManager::from_raw(cef_cookie_manager_get_global_manager(null_mut())) }
+    }
+
+    /// Get all the cookies that would be sent with a request to `url`
+    pub fn get_cookies(
+        &self,
+        url: &str,
+        include_http_only: bool,
+    ) -> impl Future<Output = Result<Vec<Cookie>, Box<dyn std::error::Error + Send + Sync>>> {
+        let (visitor, receiver) = allocate_visitor();
+        let visiting = unsafe {
+            let mut cef_url = to_cef_string(url);
+            (*visitor).inc_ref();
+            let visiting = (*self.manager)
+                .visit_url_cookies
+                .expect("visit_url_cookies is a function")(
+                self.manager,
+                &cef_url,
+                include_http_only as i32,
+                visitor as *mut cef_cookie_visitor_t,
+            );
+            cef_string_utf16_clear(&mut cef_url);
+            visiting == 1
+        };
+        finish_visit(visitor, visiting, receiver)
+    }
+
+    /// Get every cookie in the store
+    pub fn visit_all(
+        &self,
+    ) -> impl Future<Output = Result<Vec<Cookie>, Box<dyn std::error::Error + Send + Sync>>> {
+        let (visitor, receiver) = allocate_visitor();
+        let visiting = unsafe {
+            (*visitor).inc_ref();
+            (*self.manager)
+                .visit_all_cookies
+                .expect("visit_all_cookies is a function")(
+                self.manager,
+                visitor as *mut cef_cookie_visitor_t,
+            ) == 1
+        };
+        finish_visit(visitor, visiting, receiver)
+    }
+
+    /// Set a cookie for `url`, overwriting any matching cookie
+    pub fn set_cookie(
+        &self,
+        url: &str,
+        cookie: &Cookie,
+    ) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> {
+        let (sender, receiver) = oneshot::channel();
+        let callback = set_cookie_callback::allocate(Some(Box::from(move |ok: bool| {
+            let _ = sender.send(ok);
+        })));
+        let accepted = unsafe {
+            let mut cef_url = to_cef_string(url);
+            let mut cef_cookie = cookie.to_cef();
+            let accepted = (*self.manager).set_cookie.expect("set_cookie is a function")(
+                self.manager,
+                &cef_url,
+                &cef_cookie,
+                callback as *mut cef_set_cookie_callback_t,
+            );
+            cef_string_utf16_clear(&mut cef_url);
+            cef_string_utf16_clear(&mut cef_cookie.name);
+            cef_string_utf16_clear(&mut cef_cookie.value);
+            cef_string_utf16_clear(&mut cef_cookie.domain);
+            cef_string_utf16_clear(&mut cef_cookie.path);
+            accepted == 1
+        };
+        let url = url.to_owned();
+        async move {
+            if !accepted {
+                return Err(Box::from(format!("invalid cookie for `{}`", url)));
+            }
+            match receiver.await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(Box::from(format!("failed to set cookie for `{}`", url))),
+                Err(_) => Err(Box::from("cookie manager went away")),
+            }
+        }
+    }
+
+    /// Delete the cookies matching `url` and `name`, resolving to the number
+    /// of deleted cookies. Passing an empty url deletes every cookie
+    pub fn delete_cookies(
+        &self,
+        url: &str,
+        name: Option<&str>,
+    ) -> impl Future<Output = Result<usize, Box<dyn std::error::Error + Send + Sync>>> {
+        let (sender, receiver) = oneshot::channel();
+        let callback = delete_cookies_callback::allocate(Some(Box::from(move |num_deleted: usize| {
+            let _ = sender.send(num_deleted);
+        })));
+        let accepted = unsafe {
+            let mut cef_url = to_cef_string(url);
+            let mut cef_name = to_cef_string(name.unwrap_or(""));
+            let accepted = (*self.manager)
+                .delete_cookies
+                .expect("delete_cookies is a function")(
+                self.manager,
+                &cef_url,
+                &cef_name,
+                callback as *mut cef_delete_cookies_callback_t,
+            );
+            cef_string_utf16_clear(&mut cef_url);
+            cef_string_utf16_clear(&mut cef_name);
+            accepted == 1
+        };
+        async move {
+            if !accepted {
+                return Err(Box::from("failed to delete cookies"));
+            }
+            receiver.await.map_err(|_| Box::from("cookie manager went away"))
+        }
+    }
+
+    /// Write any pending changes to disk
+    pub fn flush_store(&self) -> impl Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> {
+        let (sender, receiver) = oneshot::channel();
+        let callback = completion_callback::allocate(Some(Box::from(move || {
+            let _ = sender.send(());
+        })));
+        let accepted = unsafe {
+            (*self.manager).flush_store.expect("flush_store is a function")(
+                self.manager,
+                callback as *mut cef_completion_callback_t,
+            ) == 1
+        };
+        async move {
+            if !accepted {
+                return Err(Box::from("failed to flush cookie store"));
+            }
+            receiver.await.map_err(|_| Box::from("cookie manager went away"))
+        }
+    }
+}
+
+fn allocate_visitor() -> (
+    *mut cookie_visitor::CookieVisitor,
+    oneshot::Receiver<Vec<Cookie>>,
+) {
+    let (sender, receiver) = oneshot::channel();
+    let visitor = cookie_visitor::allocate(Some(Box::from(move |cookies: Vec<Cookie>| {
+        let _ = sender.send(cookies);
+    })));
+    (visitor, receiver)
+}
+
+/// Drop our extra reference to the visitor, and resolve immediately if CEF
+/// refused to visit anything
+fn finish_visit(
+    visitor: *mut cookie_visitor::CookieVisitor,
+    visiting: bool,
+    receiver: oneshot::Receiver<Vec<Cookie>>,
+) -> impl Future<Output = Result<Vec<Cookie>, Box<dyn std::error::Error + Send + Sync>>> {
+    unsafe {
+        if !visiting {
+            // nobody is going to hear about these
+            drop(cookie_visitor::take_on_done(visitor));
+        }
+        let base = visitor as *mut super::bindings::cef_base_ref_counted_t;
+        (*base).release.expect("release is a function")(base);
+    }
+    async move {
+        if !visiting {
+            return Err(Box::from("cookies can't be accessed"));
+        }
+        receiver.await.map_err(|_| Box::from("cookie manager went away"))
+    }
+}
+
+impl Clone for CookieManager {
+    fn clone(&self) -> CookieManager {
+        unsafe {
+            (*self.manager).base.add_ref.expect("add_ref is a function")(&mut (*self.manager).base);
+        }
+        CookieManager {
+            manager: self.manager,
+        }
+    }
+}
+
+impl Drop for CookieManager {
+    fn drop(&mut self) {
+        unsafe {
+            (*self.manager).base.release.expect("release is a function")(&mut (*self.manager).base);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::mem::size_of;
+use std::os::raw::{c_int};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::bindings::{
+    cef_base_ref_counted_t, cef_cookie_t, cef_cookie_visitor_t,
+};
+use super::cookie_manager::Cookie;
+
+/// Collects every visited cookie, handing them all over once CEF is done with
+/// the visitor
+#[repr(C)]
+pub struct CookieVisitor {
+    cookie_visitor: cef_cookie_visitor_t,
+    ref_count: AtomicUsize,
+    cookies: Vec<Cookie>,
+    on_done: Option<Box<dyn FnOnce(Vec<Cookie>)>>,
+}
+
+impl CookieVisitor {
+    pub fn inc_ref(&self) {
+        self.ref_count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+unsafe extern "C" fn visit(
+    slf: *mut cef_cookie_visitor_t,
+    cookie: *const cef_cookie_t,
+    _count: c_int,
+    _total: c_int,
+    _delete_cookie: *mut c_int,
+) -> c_int {
+    let visitor = slf as *mut CookieVisitor;
+    (*visitor).cookies.push(Cookie::from_cef(&*cookie));
+    1
+}
+
+pub fn allocate(on_done: Option<Box<dyn FnOnce(Vec<Cookie>)>>) -> *mut CookieVisitor {
+    let handler = CookieVisitor {
+        cookie_visitor: cef_cookie_visitor_t {
+            base: cef_base_ref_counted_t {
+                size: size_of::<CookieVisitor>() as u64,
+                add_ref: Some(add_ref_cookie_visitor),
+                release: Some(release_cookie_visitor),
+                has_one_ref: Some(has_one_ref_cookie_visitor),
+                has_at_least_one_ref: Some(has_at_least_one_ref_cookie_visitor),
+            },
+            visit: Some(visit),
+        },
+        ref_count: AtomicUsize::new(1),
+        cookies: Vec::new(),
+        on_done,
+    };
+
+    super::ref_counting::allocated("CookieVisitor");
+    Box::into_raw(Box::from(handler))
+}
+
+/// Take the listener back out of the visitor, i.e. when CEF refused to visit
+pub unsafe fn take_on_done(slf: *mut CookieVisitor) -> Option<Box<dyn FnOnce(Vec<Cookie>)>> {
+    (*slf).on_done.take()
+}
+
+extern "C" fn add_ref_cookie_visitor(base: *mut cef_base_ref_counted_t) {
+    let visitor = base as *mut CookieVisitor;
+    unsafe {
+        (*visitor)
+            .ref_count
+            .fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+extern "C" fn release_cookie_visitor(base: *mut cef_base_ref_counted_t) -> c_int {
+    let visitor = base as *mut CookieVisitor;
+    let count = unsafe {
+        (*visitor)
+            .ref_count
+            .fetch_sub(1, Ordering::SeqCst)
+            - 1
+    };
+
+    if count == 0 {
+        // CEF releases the visitor once there is nothing left to visit, which
+        // is the only way to know that we've seen every cookie
+        let visitor = unsafe { Box::from_raw(visitor) };
+        super::ref_counting::freed("CookieVisitor");
+        let CookieVisitor { cookies, on_done, .. } = *visitor;
+        if let Some(on_done) = on_done {
+            on_done(cookies);
+        }
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_one_ref_cookie_visitor(base: *mut cef_base_ref_counted_t) -> c_int {
+    let visitor = base as *mut CookieVisitor;
+    let count = unsafe { (*visitor).ref_count.load(Ordering::SeqCst) };
+    if count == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_at_least_one_ref_cookie_visitor(base: *mut cef_base_ref_counted_t) -> c_int {
+    let visitor = base as *mut CookieVisitor;
+    let count = unsafe { (*visitor).ref_count.load(Ordering::SeqCst) };
+    if count >= 1 {
+        1
+    } else {
+        0
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::bindings::{
+    cef_browser_t, cef_termination_status_t,
+    cef_termination_status_t_TS_ABNORMAL_TERMINATION, cef_termination_status_t_TS_PROCESS_CRASHED,
+    cef_termination_status_t_TS_PROCESS_OOM, cef_termination_status_t_TS_PROCESS_WAS_KILLED,
+};
+use super::browser_handle::navigate;
+use super::value::from_cef_userfree;
+
+/// Why a render process went away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminationStatus {
+    /// Non-zero exit status
+    AbnormalTermination,
+    /// i.e. SIGKILL or the task manager
+    WasKilled,
+    /// i.e. a segmentation fault
+    Crashed,
+    /// Ran out of memory
+    OutOfMemory,
+    /// A status this version doesn't know about
+    Other,
+}
+
+/// Sent when the render process of a browser terminates unexpectedly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderProcessTerminated {
+    pub status: TerminationStatus,
+    pub browser_id: i32,
+    /// The URL the browser was showing
+    pub url: String,
+}
+
+/// What to do once a render process terminates
+pub enum RecoveryAction {
+    /// Leave the browser be
+    Nothing,
+    /// Load the URL the browser was showing again
+    Reload,
+    /// Load the HTML returned by the closure instead
+    ErrorPage(Box<dyn FnMut(&RenderProcessTerminated) -> String>),
+    /// Let the closure deal with it
+    Callback(Box<dyn FnMut(&RenderProcessTerminated)>),
+}
+
+/// Recovers browsers from render process crashes, giving up once they crash
+/// too often within a time window so that a page that crashes immediately
+/// doesn't get reloaded forever
+pub struct CrashRecovery {
+    action: RecoveryAction,
+    max_crashes: usize,
+    window: Duration,
+    crashes: VecDeque<Instant>,
+    on_give_up: Option<Box<dyn FnMut(&RenderProcessTerminated)>>,
+}
+
+impl CrashRecovery {
+    /// By default, give up after 3 crashes within a minute
+    pub fn new(action: RecoveryAction) -> CrashRecovery {
+        CrashRecovery {
+            action,
+            max_crashes: 3,
+            window: Duration::from_secs(60),
+            crashes: VecDeque::new(),
+            on_give_up: None,
+        }
+    }
+
+    pub fn reload() -> CrashRecovery {
+        CrashRecovery::new(RecoveryAction::Reload)
+    }
+
+    pub fn error_page<F: FnMut(&RenderProcessTerminated) -> String + 'static>(page: F) -> CrashRecovery {
+        CrashRecovery::new(RecoveryAction::ErrorPage(Box::from(page)))
+    }
+
+    pub fn callback<F: FnMut(&RenderProcessTerminated) + 'static>(callback: F) -> CrashRecovery {
+        CrashRecovery::new(RecoveryAction::Callback(Box::from(callback)))
+    }
+
+    /// Stop recovering once there have been `max_crashes` within `window`,
+    /// i.e. the `max_crashes`th crash is no longer recovered from
+    pub fn give_up_after(mut self, max_crashes: usize, window: Duration) -> CrashRecovery {
+        self.max_crashes = max_crashes;
+        self.window = window;
+        self
+    }
+
+    /// Called instead of recovering once we've given up
+    pub fn on_give_up<F: FnMut(&RenderProcessTerminated) + 'static>(mut self, callback: F) -> CrashRecovery {
+        self.on_give_up = Some(Box::from(callback));
+        self
+    }
+
+    /// Remember a crash at `now`, forgetting those that fell out of the
+    /// window, and tell whether to give up
+    fn record_crash(&mut self, now: Instant) -> bool {
+        while let Some(crash) = self.crashes.front() {
+            if now.duration_since(*crash) > self.window {
+                self.crashes.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.crashes.push_back(now);
+
+        self.crashes.len() >= self.max_crashes
+    }
+
+    pub(crate) unsafe fn recover(&mut self, browser: *mut cef_browser_t, event: &RenderProcessTerminated) {
+        if self.record_crash(Instant::now()) {
+            log::error!(
+                "render process for `{}` terminated {} times within {:?}, giving up",
+                event.url,
+                self.crashes.len(),
+                self.window
+            );
+            if let Some(on_give_up) = &mut self.on_give_up {
+                on_give_up(event);
+            }
+            return;
+        }
+
+        match &mut self.action {
+            RecoveryAction::Nothing => {}
+            RecoveryAction::Reload => {
+                log::info!("render process terminated, reloading `{}`", event.url);
+                navigate(browser, &event.url);
+            }
+            RecoveryAction::ErrorPage(page) => {
+                let html = page(event);
+                navigate(browser, &format!("data:text/html;base64,{}", base64::encode(&html)));
+            }
+            RecoveryAction::Callback(callback) => callback(event),
+        }
+    }
+}
+
+pub(crate) fn termination_status(status: cef_termination_status_t) -> TerminationStatus {
+    #[allow(non_upper_case_globals)]
+    match status {
+        cef_termination_status_t_TS_ABNORMAL_TERMINATION => TerminationStatus::AbnormalTermination,
+        cef_termination_status_t_TS_PROCESS_WAS_KILLED => TerminationStatus::WasKilled,
+        cef_termination_status_t_TS_PROCESS_CRASHED => TerminationStatus::Crashed,
+        cef_termination_status_t_TS_PROCESS_OOM => TerminationStatus::OutOfMemory,
+        _ => TerminationStatus::Other,
+    }
+}
+
+/// The URL the main frame of the browser is showing
+pub(crate) unsafe fn main_frame_url(browser: *mut cef_browser_t) -> String {
+    let frame = (*browser).get_main_frame.expect("get_main_frame is a function")(browser);
+    if frame.is_null() {
+        return String::new();
+    }
+    let url = from_cef_userfree((*frame).get_url.expect("get_url is a function")(frame));
+    (*frame).base.release.expect("release is a function")(&mut (*frame).base);
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::CrashRecovery;
+
+    #[test]
+    fn gives_up_at_max_crashes() {
+        let mut recovery = CrashRecovery::reload().give_up_after(3, Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(!recovery.record_crash(start));
+        assert!(!recovery.record_crash(start + Duration::from_secs(1)));
+        assert!(recovery.record_crash(start + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn forgets_crashes_outside_the_window() {
+        let mut recovery = CrashRecovery::reload().give_up_after(2, Duration::from_secs(10));
+        let start = Instant::now();
+        assert!(!recovery.record_crash(start));
+        assert!(!recovery.record_crash(start + Duration::from_secs(11)));
+        assert!(!recovery.record_crash(start + Duration::from_secs(22)));
+        assert!(recovery.record_crash(start + Duration::from_secs(23)));
+    }
+
+    #[test]
+    fn a_single_allowed_crash_gives_up_immediately() {
+        let mut recovery = CrashRecovery::reload().give_up_after(1, Duration::from_secs(60));
+        assert!(recovery.record_crash(Instant::now()));
+    }
+}
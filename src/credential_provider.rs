@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+
+use super::bindings::{cef_auth_callback_t, cef_string_utf16_clear};
+use super::value::to_cef_string;
+
+/// A server or proxy asking for credentials
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthRequest {
+    /// The origin of the request that needs authentication
+    pub origin_url: String,
+    /// Whether a proxy is asking, rather than the server itself
+    pub is_proxy: bool,
+    pub host: String,
+    pub port: u16,
+    pub realm: String,
+    /// The authentication scheme, i.e. `basic` or `digest`
+    pub scheme: String,
+}
+
+/// The answer to an `AuthRequest`. Dropping the callback without answering
+/// cancels the request
+pub struct AuthCallback {
+    callback: *mut cef_auth_callback_t,
+}
+
+impl AuthCallback {
+    /// Answer with a username and password
+    pub fn cont(mut self, username: &str, password: &str) {
+        unsafe {
+            let mut cef_username = to_cef_string(username);
+            let mut cef_password = to_cef_string(password);
+            (*self.callback).cont.expect("cont is a function")(self.callback, &cef_username, &cef_password);
+            cef_string_utf16_clear(&mut cef_username);
+            cef_string_utf16_clear(&mut cef_password);
+        }
+        self.finish();
+    }
+
+    /// Refuse to authenticate
+    pub fn cancel(mut self) {
+        unsafe {
+            (*self.callback).cancel.expect("cancel is a function")(self.callback);
+        }
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        unsafe {
+            (*self.callback).base.release.expect("release is a function")(&mut (*self.callback).base);
+        }
+        self.callback = std::ptr::null_mut();
+    }
+}
+
+impl Drop for AuthCallback {
+    fn drop(&mut self) {
+        if !self.callback.is_null() {
+            unsafe {
+                (*self.callback).cancel.expect("cancel is a function")(self.callback);
+            }
+            self.finish();
+        }
+    }
+}
+
+/// Supplies credentials for HTTP authentication. The callback can be answered
+/// asynchronously, but must be answered on the UI thread
+pub trait CredentialProvider {
+    fn provide_credentials(&mut self, request: &AuthRequest, callback: AuthCallback);
+}
+
+/// A username and password
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Credentials kept in memory, keyed by host and realm
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryCredentials {
+    credentials: HashMap<(String, String), Credentials>,
+}
+
+impl InMemoryCredentials {
+    pub fn new() -> InMemoryCredentials {
+        InMemoryCredentials::default()
+    }
+
+    /// Use these credentials whenever `host` asks for them for `realm`
+    pub fn insert(&mut self, host: &str, realm: &str, username: &str, password: &str) {
+        self.credentials.insert(
+            (host.to_owned(), realm.to_owned()),
+            Credentials {
+                username: username.to_owned(),
+                password: password.to_owned(),
+            },
+        );
+    }
+
+    pub fn remove(&mut self, host: &str, realm: &str) -> Option<Credentials> {
+        self.credentials.remove(&(host.to_owned(), realm.to_owned()))
+    }
+}
+
+impl CredentialProvider for InMemoryCredentials {
+    fn provide_credentials(&mut self, request: &AuthRequest, callback: AuthCallback) {
+        match self.credentials.get(&(request.host.clone(), request.realm.clone())) {
+            Some(credentials) => callback.cont(&credentials.username, &credentials.password),
+            None => {
+                log::debug!("no credentials for `{}` ({})", request.host, request.realm);
+                callback.cancel();
+            }
+        }
+    }
+}
+
+/// Hand an authentication request over to the provider
+pub unsafe fn provide_credentials(
+    provider: &mut dyn CredentialProvider,
+    request: AuthRequest,
+    callback: *mut cef_auth_callback_t,
+) {
+    (*callback).base.add_ref.expect("add_ref is a function")(&mut (*callback).base);
+    provider.provide_credentials(&request, AuthCallback { callback });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::raw::c_int;
+
+    use super::{provide_credentials, AuthRequest, InMemoryCredentials};
+    use crate::bindings::{cef_auth_callback_t, cef_base_ref_counted_t, cef_string_t};
+    use crate::value::from_cef_string;
+
+    #[derive(Debug, PartialEq)]
+    enum Answer {
+        Cont(String, String),
+        Cancel,
+    }
+
+    /// A CEF callback that remembers how it was answered
+    #[repr(C)]
+    struct FakeCallback {
+        callback: cef_auth_callback_t,
+        ref_count: isize,
+        answer: Option<Answer>,
+    }
+
+    unsafe extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
+        (*(base as *mut FakeCallback)).ref_count += 1;
+    }
+
+    unsafe extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
+        (*(base as *mut FakeCallback)).ref_count -= 1;
+        0
+    }
+
+    unsafe extern "C" fn cont(slf: *mut cef_auth_callback_t, username: *const cef_string_t, password: *const cef_string_t) {
+        (*(slf as *mut FakeCallback)).answer = Some(Answer::Cont(from_cef_string(username), from_cef_string(password)));
+    }
+
+    unsafe extern "C" fn cancel(slf: *mut cef_auth_callback_t) {
+        (*(slf as *mut FakeCallback)).answer = Some(Answer::Cancel);
+    }
+
+    fn ask(provider: &mut InMemoryCredentials, host: &str, realm: &str) -> Answer {
+        let mut fake = FakeCallback {
+            callback: cef_auth_callback_t::default(),
+            ref_count: 0,
+            answer: None,
+        };
+        fake.callback.base.add_ref = Some(add_ref);
+        fake.callback.base.release = Some(release);
+        fake.callback.cont = Some(cont);
+        fake.callback.cancel = Some(cancel);
+        let request = AuthRequest {
+            origin_url: format!("https://{}/", host),
+            is_proxy: false,
+            host: host.to_owned(),
+            port: 443,
+            realm: realm.to_owned(),
+            scheme: "basic".to_owned(),
+        };
+        unsafe { provide_credentials(provider, request, &mut fake.callback) };
+        assert_eq!(fake.ref_count, 0);
+        fake.answer.expect("the callback is answered")
+    }
+
+    #[test]
+    fn answers_matching_host_and_realm() {
+        let mut credentials = InMemoryCredentials::new();
+        credentials.insert("example.com", "admin", "user", "secret");
+        assert_eq!(
+            ask(&mut credentials, "example.com", "admin"),
+            Answer::Cont("user".to_owned(), "secret".to_owned())
+        );
+    }
+
+    #[test]
+    fn cancels_for_another_realm() {
+        let mut credentials = InMemoryCredentials::new();
+        credentials.insert("example.com", "admin", "user", "secret");
+        assert_eq!(ask(&mut credentials, "example.com", "other"), Answer::Cancel);
+        assert_eq!(ask(&mut credentials, "example.org", "admin"), Answer::Cancel);
+    }
+
+    #[test]
+    fn cancels_once_removed() {
+        let mut credentials = InMemoryCredentials::new();
+        credentials.insert("example.com", "admin", "user", "secret");
+        let removed = credentials.remove("example.com", "admin").unwrap();
+        assert_eq!(removed.username, "user");
+        assert_eq!(removed.password, "secret");
+        assert_eq!(ask(&mut credentials, "example.com", "admin"), Answer::Cancel);
+        assert!(credentials.remove("example.com", "admin").is_none());
+    }
+}
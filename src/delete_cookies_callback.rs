@@ -0,0 +1,90 @@
+use std::mem::size_of;
+use std::os::raw::{c_int};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::bindings::{
+    cef_base_ref_counted_t, cef_delete_cookies_callback_t,
+};
+
+#[repr(C)]
+pub struct DeleteCookiesCallback {
+    delete_cookies_callback: cef_delete_cookies_callback_t,
+    ref_count: AtomicUsize,
+    on_done: Option<Box<dyn FnOnce(usize)>>,
+}
+
+unsafe extern "C" fn on_complete(slf: *mut cef_delete_cookies_callback_t, num_deleted: c_int) {
+    let callback = slf as *mut DeleteCookiesCallback;
+    if let Some(on_done) = (*callback).on_done.take() {
+        on_done(num_deleted.max(0) as usize);
+    }
+}
+
+pub fn allocate(on_done: Option<Box<dyn FnOnce(usize)>>) -> *mut DeleteCookiesCallback {
+    let handler = DeleteCookiesCallback {
+        delete_cookies_callback: cef_delete_cookies_callback_t {
+            base: cef_base_ref_counted_t {
+                size: size_of::<DeleteCookiesCallback>() as u64,
+                add_ref: Some(add_ref_delete_cookies_callback),
+                release: Some(release_delete_cookies_callback),
+                has_one_ref: Some(has_one_ref_delete_cookies_callback),
+                has_at_least_one_ref: Some(has_at_least_one_ref_delete_cookies_callback),
+            },
+            on_complete: Some(on_complete),
+        },
+        ref_count: AtomicUsize::new(1),
+        on_done,
+    };
+
+    super::ref_counting::allocated("DeleteCookiesCallback");
+    Box::into_raw(Box::from(handler))
+}
+
+extern "C" fn add_ref_delete_cookies_callback(base: *mut cef_base_ref_counted_t) {
+    let callback = base as *mut DeleteCookiesCallback;
+    unsafe {
+        (*callback)
+            .ref_count
+            .fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+extern "C" fn release_delete_cookies_callback(base: *mut cef_base_ref_counted_t) -> c_int {
+    let callback = base as *mut DeleteCookiesCallback;
+    let count = unsafe {
+        (*callback)
+            .ref_count
+            .fetch_sub(1, Ordering::SeqCst)
+            - 1
+    };
+
+    if count == 0 {
+        unsafe {
+            Box::from_raw(callback);
+            super::ref_counting::freed("DeleteCookiesCallback");
+        }
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_one_ref_delete_cookies_callback(base: *mut cef_base_ref_counted_t) -> c_int {
+    let callback = base as *mut DeleteCookiesCallback;
+    let count = unsafe { (*callback).ref_count.load(Ordering::SeqCst) };
+    if count == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_at_least_one_ref_delete_cookies_callback(base: *mut cef_base_ref_counted_t) -> c_int {
+    let callback = base as *mut DeleteCookiesCallback;
+    let count = unsafe { (*callback).ref_count.load(Ordering::SeqCst) };
+    if count >= 1 {
+        1
+    } else {
+        0
+    }
+}
+
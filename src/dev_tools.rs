@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::os::raw::{c_int, c_void};
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::channel::oneshot;
+use serde_json::Value;
+
+use super::bindings::{
+    cef_base_ref_counted_t, cef_browser_host_t, cef_browser_t, cef_dev_tools_message_observer_t,
+    cef_registration_t, cef_string_t, cef_string_utf16_clear, size_t,
+};
+use super::value;
+
+type MethodResult = Result<Value, String>;
+
+/// Receives the results of DevTools protocol methods executed on a browser
+#[repr(C)]
+pub struct DevToolsObserver {
+    observer: cef_dev_tools_message_observer_t,
+    ref_count: AtomicUsize,
+    registration: *mut cef_registration_t,
+    next_message_id: c_int,
+    pending: HashMap<c_int, oneshot::Sender<MethodResult>>,
+}
+
+impl DevToolsObserver {
+    pub fn inc_ref(&self) {
+        self.ref_count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+extern "C" fn on_dev_tools_message(
+    _slf: *mut cef_dev_tools_message_observer_t,
+    _browser: *mut cef_browser_t,
+    _message: *const c_void,
+    _message_size: size_t,
+) -> c_int {
+    // let the results get routed to on_dev_tools_method_result
+    0
+}
+
+unsafe extern "C" fn on_dev_tools_method_result(
+    slf: *mut cef_dev_tools_message_observer_t,
+    _browser: *mut cef_browser_t,
+    message_id: c_int,
+    success: c_int,
+    result: *const c_void,
+    result_size: size_t,
+) {
+    let observer = slf as *mut DevToolsObserver;
+    let sender = match (*observer).pending.remove(&message_id) {
+        Some(sender) => sender,
+        None => return,
+    };
+
+    let result = std::slice::from_raw_parts(result as *const u8, result_size as usize);
+    let result = match (success == 1, serde_json::from_slice::<Value>(result)) {
+        (true, Ok(value)) => Ok(value),
+        (false, Ok(value)) => Err(value["message"]
+            .as_str()
+            .unwrap_or("unknown dev tools error")
+            .to_owned()),
+        (_, Err(e)) => Err(format!("failed to parse dev tools result: {}", e)),
+    };
+    let _ = sender.send(result);
+}
+
+extern "C" fn on_dev_tools_event(
+    _slf: *mut cef_dev_tools_message_observer_t,
+    _browser: *mut cef_browser_t,
+    _method: *const cef_string_t,
+    _params: *const c_void,
+    _params_size: size_t,
+) {
+}
+
+extern "C" fn on_dev_tools_agent_attached(
+    _slf: *mut cef_dev_tools_message_observer_t,
+    _browser: *mut cef_browser_t,
+) {
+}
+
+unsafe extern "C" fn on_dev_tools_agent_detached(
+    slf: *mut cef_dev_tools_message_observer_t,
+    _browser: *mut cef_browser_t,
+) {
+    // nothing pending is going to get an answer now
+    let observer = slf as *mut DevToolsObserver;
+    for (_, sender) in (*observer).pending.drain() {
+        let _ = sender.send(Err("dev tools agent detached".to_owned()));
+    }
+}
+
+pub fn allocate() -> *mut DevToolsObserver {
+    let observer = DevToolsObserver {
+        observer: cef_dev_tools_message_observer_t {
+            base: cef_base_ref_counted_t {
+                size: size_of::<DevToolsObserver>() as u64,
+                add_ref: Some(add_ref),
+                release: Some(release),
+                has_one_ref: Some(has_one_ref),
+                has_at_least_one_ref: Some(has_at_least_one_ref),
+            },
+            on_dev_tools_message: Some(on_dev_tools_message),
+            on_dev_tools_method_result: Some(on_dev_tools_method_result),
+            on_dev_tools_event: Some(on_dev_tools_event),
+            on_dev_tools_agent_attached: Some(on_dev_tools_agent_attached),
+            on_dev_tools_agent_detached: Some(on_dev_tools_agent_detached),
+        },
+        ref_count: AtomicUsize::new(1),
+        registration: null_mut(),
+        next_message_id: 0,
+        pending: HashMap::new(),
+    };
+
+    super::ref_counting::allocated("DevToolsObserver");
+    Box::into_raw(Box::from(observer))
+}
+
+/// Execute a DevTools protocol method on the browser. Must be called on the
+/// UI thread; the returned receiver resolves with the method's result object
+pub unsafe fn execute_method(
+    slf: *mut DevToolsObserver,
+    host: *mut cef_browser_host_t,
+    method: &str,
+    params: &Value,
+) -> oneshot::Receiver<MethodResult> {
+    let (sender, receiver) = oneshot::channel();
+
+    // start listening for results the first time we're used
+    if (*slf).registration.is_null() {
+        (*slf).inc_ref();
+        (*slf).registration = (*host)
+            .add_dev_tools_message_observer
+            .expect("add_dev_tools_message_observer is a function")(
+            host,
+            slf as *mut cef_dev_tools_message_observer_t,
+        );
+    }
+
+    (*slf).next_message_id += 1;
+    let message_id = (*slf).next_message_id;
+    (*slf).pending.insert(message_id, sender);
+
+    let mut cef_method = value::to_cef_string(method);
+    let cef_params = match params {
+        Value::Object(map) => value::json_to_cef_dictionary(map),
+        _ => null_mut(),
+    };
+    let sent = (*host)
+        .execute_dev_tools_method
+        .expect("execute_dev_tools_method is a function")(
+        host, message_id, &cef_method, cef_params
+    );
+    cef_string_utf16_clear(&mut cef_method);
+
+    if sent == 0 {
+        if let Some(sender) = (*slf).pending.remove(&message_id) {
+            let _ = sender.send(Err(format!("failed to execute dev tools method `{}`", method)));
+        }
+    }
+
+    receiver
+}
+
+/// Stop observing, releasing the registration and with it the reference it
+/// holds to the observer
+pub unsafe fn unregister(slf: *mut DevToolsObserver) {
+    let registration = std::mem::replace(&mut (*slf).registration, null_mut());
+    super::ref_counting::release(registration);
+}
+
+extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
+    let observer = base as *mut DevToolsObserver;
+    unsafe { (*observer).ref_count.fetch_add(1, Ordering::SeqCst) };
+}
+
+extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
+    let observer = base as *mut DevToolsObserver;
+    let count = unsafe { (*observer).ref_count.fetch_sub(1, Ordering::SeqCst) - 1 };
+
+    if count == 0 {
+        unsafe {
+            Box::from_raw(observer);
+            super::ref_counting::freed("DevToolsObserver");
+        }
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let observer = base as *mut DevToolsObserver;
+    let count = unsafe { (*observer).ref_count.load(Ordering::SeqCst) };
+    if count == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_at_least_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let observer = base as *mut DevToolsObserver;
+    let count = unsafe { (*observer).ref_count.load(Ordering::SeqCst) };
+    if count >= 1 {
+        1
+    } else {
+        0
+    }
+}
@@ -0,0 +1,118 @@
+use std::future::Future;
+
+use serde_json::json;
+
+use super::bindings::cef_browser_host_t;
+use super::client::{Client, DevToolsTarget};
+use super::pdf_print_settings::{Margins, PaperSize};
+
+const MICRONS_PER_INCH: f64 = 25400.0;
+const MM_PER_INCH: f64 = 25.4;
+
+/// Options for `Browser::print_to_pdf_dev_tools`, which can do a few things
+/// `PdfPrintSettings` can't
+///
+/// The header and footer templates are HTML, into which Chrome fills any
+/// elements with the classes `date`, `title`, `url`, `pageNumber` and
+/// `totalPages`. For example, a "Page X of Y" footer:
+///
+/// ```html
+/// <div style="font-size: 10px; width: 100%; text-align: center;">
+///     Page <span class="pageNumber"></span> of <span class="totalPages"></span>
+/// </div>
+/// ```
+///
+/// Note that the templates don't inherit the page's styles and default to a
+/// font size of zero, so always give them one
+#[derive(Debug, Clone, PartialEq)]
+pub struct DevToolsPdfOptions {
+    pub paper_size: PaperSize,
+    pub landscape: bool,
+    /// Scale the page, `1.0` being 100%
+    pub scale: f64,
+    pub margins: Margins,
+    /// Pages to print such as `"1-5, 8, 11-13"`, empty for all of them
+    pub page_ranges: String,
+    /// HTML for the header, only shown when either template is set
+    pub header_template: Option<String>,
+    /// HTML for the footer, only shown when either template is set
+    pub footer_template: Option<String>,
+    /// Print background colours and images
+    pub backgrounds: bool,
+    /// Use the page size given by the page's CSS `@page` rule, if any, over
+    /// `paper_size`
+    pub prefer_css_page_size: bool,
+}
+
+impl Default for DevToolsPdfOptions {
+    fn default() -> DevToolsPdfOptions {
+        DevToolsPdfOptions {
+            paper_size: PaperSize::A4,
+            landscape: false,
+            scale: 1.0,
+            margins: Margins::Default,
+            page_ranges: String::new(),
+            header_template: None,
+            footer_template: None,
+            backgrounds: true,
+            prefer_css_page_size: false,
+        }
+    }
+}
+
+/// Print to PDF using the DevTools `Page.printToPDF` method, resolving to the
+/// PDF's bytes. The client and host are kept alive until the future completes
+pub unsafe fn print(
+    client: *mut Client,
+    host: *mut cef_browser_host_t,
+    options: DevToolsPdfOptions,
+) -> impl Future<Output = Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>> {
+    let target = DevToolsTarget::new(client, host);
+    async move {
+        let (width, height) = options.paper_size.microns();
+        let mut params = json!({
+            "landscape": options.landscape,
+            "printBackground": options.backgrounds,
+            "scale": options.scale,
+            "paperWidth": width as f64 / MICRONS_PER_INCH,
+            "paperHeight": height as f64 / MICRONS_PER_INCH,
+            "pageRanges": options.page_ranges,
+            "preferCSSPageSize": options.prefer_css_page_size,
+        });
+
+        // leaving the margins out gives Chrome's defaults
+        let margins = match options.margins {
+            Margins::Default => None,
+            Margins::None | Margins::Minimum => Some((0.0, 0.0, 0.0, 0.0)),
+            Margins::Custom {
+                top,
+                right,
+                bottom,
+                left,
+            } => Some((top, right, bottom, left)),
+        };
+        if let Some((top, right, bottom, left)) = margins {
+            params["marginTop"] = json!(top / MM_PER_INCH);
+            params["marginRight"] = json!(right / MM_PER_INCH);
+            params["marginBottom"] = json!(bottom / MM_PER_INCH);
+            params["marginLeft"] = json!(left / MM_PER_INCH);
+        }
+
+        if options.header_template.is_some() || options.footer_template.is_some() {
+            params["displayHeaderFooter"] = json!(true);
+            // an empty template would fall back to Chrome's own header / footer
+            params["headerTemplate"] = json!(options
+                .header_template
+                .unwrap_or_else(|| "<span></span>".to_owned()));
+            params["footerTemplate"] = json!(options
+                .footer_template
+                .unwrap_or_else(|| "<span></span>".to_owned()));
+        }
+
+        let result = target.execute("Page.printToPDF", params).await?;
+        let data = result["data"]
+            .as_str()
+            .ok_or("print result didn't contain any data")?;
+        Ok(base64::decode(data)?)
+    }
+}
@@ -0,0 +1,220 @@
+use std::mem::size_of;
+use std::os::raw::c_int;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::bindings::{
+    cef_base_ref_counted_t, cef_browser_t, cef_dialog_handler_t, cef_file_dialog_callback_t,
+    cef_file_dialog_mode_t, cef_file_dialog_mode_t_FILE_DIALOG_OVERWRITEPROMPT_FLAG,
+    cef_string_list_t, cef_string_t,
+};
+use super::file_dialog::{
+    self, FileDialogHandler, FileDialogMode, FileDialogOptions, FileDialogRequest, FileFilter,
+};
+use super::value::{from_cef_string, from_cef_string_list};
+
+#[repr(C)]
+pub struct DialogHandler {
+    dialog_handler: cef_dialog_handler_t,
+    ref_count: AtomicUsize,
+    file_dialog_handler: Option<Box<dyn FileDialogHandler>>,
+}
+
+impl DialogHandler {
+    pub fn inc_ref(&self) {
+        self.ref_count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+unsafe extern "C" fn on_file_dialog(
+    slf: *mut cef_dialog_handler_t,
+    browser: *mut cef_browser_t,
+    mode: cef_file_dialog_mode_t,
+    title: *const cef_string_t,
+    default_file_path: *const cef_string_t,
+    accept_filters: cef_string_list_t,
+    selected_accept_filter: c_int,
+    callback: *mut cef_file_dialog_callback_t,
+) -> c_int {
+    let handler = slf as *mut DialogHandler;
+    let file_dialog_handler = match &mut (*handler).file_dialog_handler {
+        Some(file_dialog_handler) => file_dialog_handler,
+        // show the default dialog
+        None => return 0,
+    };
+
+    let options = match options_from_cef(
+        mode,
+        from_cef_string(title),
+        from_cef_string(default_file_path),
+        from_cef_string_list(accept_filters),
+        selected_accept_filter,
+    ) {
+        Ok(options) => options,
+        Err(e) => {
+            // show the default dialog rather than guess
+            log::warn!("invalid file dialog request: {}", e);
+            return 0;
+        }
+    };
+    let request = FileDialogRequest {
+        browser_id: super::browser_registry::browser_id(browser),
+        options,
+    };
+
+    file_dialog::run_file_dialog(file_dialog_handler.as_mut(), request, callback) as c_int
+}
+
+/// Turn what CEF asked for into our options. A selected filter that isn't
+/// one of the filters is an error, as with `FileDialogOptions::from_json`
+fn options_from_cef(
+    mode: cef_file_dialog_mode_t,
+    title: String,
+    default_path: String,
+    filters: Vec<String>,
+    selected_filter: c_int,
+) -> Result<FileDialogOptions, String> {
+    let selected_filter = file_dialog::check_selected_filter(selected_filter as i64, filters.len())?;
+    Ok(FileDialogOptions {
+        mode: FileDialogMode::from_cef(mode),
+        title,
+        default_path: if default_path.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(default_path))
+        },
+        filters: filters.iter().map(|filter| FileFilter::parse(filter)).collect(),
+        selected_filter,
+        overwrite_prompt: mode & cef_file_dialog_mode_t_FILE_DIALOG_OVERWRITEPROMPT_FLAG != 0,
+    })
+}
+
+pub fn allocate() -> *mut DialogHandler {
+    let handler = DialogHandler {
+        dialog_handler: cef_dialog_handler_t {
+            base: cef_base_ref_counted_t {
+                size: size_of::<DialogHandler>() as u64,
+                add_ref: Some(add_ref),
+                release: Some(release),
+                has_one_ref: Some(has_one_ref),
+                has_at_least_one_ref: Some(has_at_least_one_ref),
+            },
+            on_file_dialog: Some(on_file_dialog),
+        },
+        ref_count: AtomicUsize::new(1),
+        file_dialog_handler: None,
+    };
+
+    super::ref_counting::allocated("DialogHandler");
+    Box::into_raw(Box::from(handler))
+}
+
+pub unsafe fn set_file_dialog_handler(
+    slf: *mut DialogHandler,
+    handler: Box<dyn FileDialogHandler>,
+) {
+    (*slf).file_dialog_handler = Some(handler);
+}
+
+extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
+    let dialog_handler = base as *mut DialogHandler;
+    unsafe {
+        (*dialog_handler).ref_count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
+    let dialog_handler = base as *mut DialogHandler;
+    let count = unsafe { (*dialog_handler).ref_count.fetch_sub(1, Ordering::SeqCst) - 1 };
+
+    if count == 0 {
+        unsafe {
+            Box::from_raw(dialog_handler);
+            super::ref_counting::freed("DialogHandler");
+        }
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let dialog_handler = base as *mut DialogHandler;
+    let count = unsafe { (*dialog_handler).ref_count.load(Ordering::SeqCst) };
+    if count == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_at_least_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let dialog_handler = base as *mut DialogHandler;
+    let count = unsafe { (*dialog_handler).ref_count.load(Ordering::SeqCst) };
+    if count >= 1 {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::options_from_cef;
+    use crate::bindings::{
+        cef_file_dialog_mode_t_FILE_DIALOG_OPEN_MULTIPLE,
+        cef_file_dialog_mode_t_FILE_DIALOG_OVERWRITEPROMPT_FLAG,
+        cef_file_dialog_mode_t_FILE_DIALOG_SAVE,
+    };
+    use crate::file_dialog::{FileDialogMode, FileFilter};
+
+    #[test]
+    fn reads_save_with_overwrite_prompt() {
+        let options = options_from_cef(
+            cef_file_dialog_mode_t_FILE_DIALOG_SAVE | cef_file_dialog_mode_t_FILE_DIALOG_OVERWRITEPROMPT_FLAG,
+            "Save".to_owned(),
+            "out.txt".to_owned(),
+            vec!["Text|.txt".to_owned()],
+            0,
+        )
+        .unwrap();
+        assert_eq!(options.mode, FileDialogMode::Save);
+        assert!(options.overwrite_prompt);
+        assert_eq!(options.title, "Save");
+        assert_eq!(options.default_path, Some(PathBuf::from("out.txt")));
+        assert_eq!(options.filters, vec![FileFilter::new("Text", &[".txt"])]);
+    }
+
+    #[test]
+    fn reads_open_multiple_without_defaults() {
+        let options = options_from_cef(
+            cef_file_dialog_mode_t_FILE_DIALOG_OPEN_MULTIPLE,
+            String::new(),
+            String::new(),
+            vec!["image/*".to_owned(), ".pdf".to_owned()],
+            1,
+        )
+        .unwrap();
+        assert_eq!(options.mode, FileDialogMode::OpenMultiple);
+        assert!(!options.overwrite_prompt);
+        assert_eq!(options.default_path, None);
+        assert_eq!(
+            options.filters,
+            vec![FileFilter::new("", &["image/*"]), FileFilter::new("", &[".pdf"])]
+        );
+        assert_eq!(options.selected_filter, 1);
+    }
+
+    #[test]
+    fn rejects_out_of_range_selected_filter() {
+        let save = cef_file_dialog_mode_t_FILE_DIALOG_SAVE;
+        let filters = vec![".txt".to_owned(), ".log".to_owned()];
+        assert!(options_from_cef(save, String::new(), String::new(), filters.clone(), -1).is_err());
+        assert!(options_from_cef(save, String::new(), String::new(), filters.clone(), 2).is_err());
+        assert!(options_from_cef(save, String::new(), String::new(), Vec::new(), 1).is_err());
+        assert!(options_from_cef(save, String::new(), String::new(), Vec::new(), 0).is_ok());
+        assert!(options_from_cef(save, String::new(), String::new(), filters, 1).is_ok());
+    }
+}
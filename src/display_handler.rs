@@ -88,6 +88,7 @@ pub fn allocate() -> *mut DisplayHandler {
         fullscreen_changed: None,
     };
 
+    super::ref_counting::allocated("DisplayHandler");
     Box::into_raw(Box::from(handler))
 }
 
@@ -108,6 +109,7 @@ extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
     if count == 0 {
         unsafe {
             Box::from_raw(display_handler);
+            super::ref_counting::freed("DisplayHandler");
         }
         1
     } else {
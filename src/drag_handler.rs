@@ -0,0 +1,244 @@
+use std::mem::size_of;
+use std::os::raw::{c_int};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::bindings::{
+    cef_base_ref_counted_t, cef_browser_t, cef_drag_data_t, cef_drag_handler_t,
+    cef_drag_operations_mask_t, cef_process_id_t_PID_RENDERER, cef_process_message_create,
+    cef_string_list_alloc, cef_string_list_free, cef_string_utf16_clear,
+};
+use super::value::{from_cef_string_list, from_cef_userfree, to_cef_string};
+
+/// The operations a drag source allows, as a bit mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DragOperations(pub u32);
+
+impl DragOperations {
+    pub const NONE: DragOperations = DragOperations(0);
+    pub const COPY: DragOperations = DragOperations(1);
+    pub const LINK: DragOperations = DragOperations(2);
+    pub const GENERIC: DragOperations = DragOperations(4);
+    pub const PRIVATE: DragOperations = DragOperations(8);
+    pub const MOVE: DragOperations = DragOperations(16);
+    pub const DELETE: DragOperations = DragOperations(32);
+
+    pub fn contains(&self, other: DragOperations) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// What is being dragged into the browser
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DragData {
+    /// Paths of any files being dragged
+    pub files: Vec<PathBuf>,
+    /// The URL, if a link is being dragged
+    pub link_url: Option<String>,
+    pub link_title: Option<String>,
+    /// The plain text, if a fragment of text or HTML is being dragged
+    pub text: Option<String>,
+    pub html: Option<String>,
+}
+
+/// Sent when something is dragged into the browser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DragEnter {
+    pub data: DragData,
+    pub allowed_ops: DragOperations,
+}
+
+/// Which kinds of drags the browser accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DragPolicy {
+    /// Accept dragged files. Unless the page handles the drop itself, Chromium
+    /// navigates the browser to a dropped file, so this is off by default
+    pub allow_files: bool,
+    pub allow_text: bool,
+    pub allow_links: bool,
+    /// Accept dragged files and hand their paths to the page's
+    /// `cef.onFilesDropped` listeners instead of navigating to them
+    pub forward_files_to_js: bool,
+}
+
+impl Default for DragPolicy {
+    fn default() -> DragPolicy {
+        DragPolicy {
+            allow_files: false,
+            allow_text: true,
+            allow_links: true,
+            forward_files_to_js: false,
+        }
+    }
+}
+
+#[repr(C)]
+pub struct DragHandler {
+    drag_handler: cef_drag_handler_t,
+    ref_count: AtomicUsize,
+    policy: DragPolicy,
+    drag_enter: Option<Box<dyn FnMut(&DragEnter) -> bool>>,
+}
+
+impl DragHandler {
+    pub fn inc_ref(&self) {
+        self.ref_count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+unsafe fn read_drag_data(drag_data: *mut cef_drag_data_t) -> DragData {
+    let mut data = DragData::default();
+    if (*drag_data).is_file.expect("is_file is a function")(drag_data) == 1 {
+        let names = cef_string_list_alloc();
+        if (*drag_data).get_file_names.expect("get_file_names is a function")(drag_data, names) == 1 {
+            data.files = from_cef_string_list(names).into_iter().map(PathBuf::from).collect();
+        }
+        cef_string_list_free(names);
+    }
+    if (*drag_data).is_link.expect("is_link is a function")(drag_data) == 1 {
+        data.link_url = Some(from_cef_userfree((*drag_data).get_link_url.expect("get_link_url is a function")(drag_data)));
+        data.link_title = Some(from_cef_userfree((*drag_data).get_link_title.expect("get_link_title is a function")(drag_data)));
+    }
+    if (*drag_data).is_fragment.expect("is_fragment is a function")(drag_data) == 1 {
+        data.text = Some(from_cef_userfree((*drag_data).get_fragment_text.expect("get_fragment_text is a function")(drag_data)));
+        data.html = Some(from_cef_userfree((*drag_data).get_fragment_html.expect("get_fragment_html is a function")(drag_data)));
+    }
+    data
+}
+
+/// Send the dragged file paths to the main frame's renderer, where only our
+/// drag and drop extension can read them
+unsafe fn forward_files_to_js(browser: *mut cef_browser_t, files: &[PathBuf]) {
+    let files: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+    let files = serde_json::to_string(&files).expect("can serialize paths");
+
+    let frame = (*browser).get_main_frame.expect("get_main_frame is a function")(browser);
+    if frame.is_null() {
+        return;
+    }
+    let mut cef_message_name = to_cef_string("dragged_files");
+    let mut cef_files = to_cef_string(&files);
+
+    let message = cef_process_message_create(&cef_message_name);
+    let args = ((*message).get_argument_list.expect("get_argument_list is a function"))(message);
+    ((*args).set_size.expect("set_size is a function"))(args, 1);
+    ((*args).set_string.expect("set_string is a function"))(args, 0, &cef_files);
+    ((*frame).send_process_message.expect("send_process_message is a function"))(frame, cef_process_id_t_PID_RENDERER, message);
+
+    cef_string_utf16_clear(&mut cef_message_name);
+    cef_string_utf16_clear(&mut cef_files);
+    (*frame).base.release.expect("release is a function")(&mut (*frame).base);
+}
+
+unsafe extern "C" fn on_drag_enter(
+    slf: *mut cef_drag_handler_t,
+    browser: *mut cef_browser_t,
+    drag_data: *mut cef_drag_data_t,
+    mask: cef_drag_operations_mask_t,
+) -> c_int {
+    let handler = slf as *mut DragHandler;
+    let policy = (*handler).policy;
+    let event = DragEnter {
+        data: read_drag_data(drag_data),
+        allowed_ops: DragOperations(mask as u32),
+    };
+
+    let has_files = !event.data.files.is_empty();
+    let allowed = if has_files {
+        policy.allow_files || policy.forward_files_to_js
+    } else if event.data.link_url.is_some() {
+        policy.allow_links
+    } else if event.data.text.is_some() {
+        policy.allow_text
+    } else {
+        true
+    };
+    let allowed = allowed
+        && match &mut (*handler).drag_enter {
+            Some(drag_enter) => drag_enter(&event),
+            None => true,
+        };
+
+    if allowed && policy.forward_files_to_js {
+        // always forward, so a drag without files clears out old paths
+        forward_files_to_js(browser, &event.data.files);
+    }
+
+    // returning 1 cancels the drag
+    if allowed {
+        0
+    } else {
+        log::debug!("rejected drag: {:?}", event.data);
+        1
+    }
+}
+
+pub fn allocate() -> *mut DragHandler {
+    let handler = DragHandler {
+        drag_handler: cef_drag_handler_t {
+            base: cef_base_ref_counted_t {
+                size: size_of::<DragHandler>() as u64,
+                add_ref: Some(add_ref),
+                release: Some(release),
+                has_one_ref: Some(has_one_ref),
+                has_at_least_one_ref: Some(has_at_least_one_ref),
+            },
+            on_drag_enter: Some(on_drag_enter),
+            on_draggable_regions_changed: None,
+        },
+        ref_count: AtomicUsize::new(1),
+        policy: DragPolicy::default(),
+        drag_enter: None,
+    };
+
+    super::ref_counting::allocated("DragHandler");
+    Box::into_raw(Box::from(handler))
+}
+
+pub unsafe fn set_policy(slf: *mut DragHandler, policy: DragPolicy) {
+    (*slf).policy = policy;
+}
+
+pub unsafe fn set_drag_enter_listener<F: FnMut(&DragEnter) -> bool + 'static>(slf: *mut DragHandler, listener: F) {
+    (*slf).drag_enter = Some(Box::from(listener));
+}
+
+extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
+    let drag_handler = base as *mut DragHandler;
+    unsafe { (*drag_handler).ref_count.fetch_add(1, Ordering::SeqCst) };
+}
+
+extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
+    let drag_handler = base as *mut DragHandler;
+    let count = unsafe { (*drag_handler).ref_count.fetch_sub(1, Ordering::SeqCst) - 1 };
+
+    if count == 0 {
+        unsafe {
+            Box::from_raw(drag_handler);
+            super::ref_counting::freed("DragHandler");
+        }
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let drag_handler = base as *mut DragHandler;
+    let count = unsafe { (*drag_handler).ref_count.load(Ordering::SeqCst) };
+    if count == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_at_least_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let drag_handler = base as *mut DragHandler;
+    let count = unsafe { (*drag_handler).ref_count.load(Ordering::SeqCst) };
+    if count >= 1 {
+        1
+    } else {
+        0
+    }
+}
@@ -0,0 +1,42 @@
+use std::future::Future;
+
+use serde_json::{json, Value};
+
+use super::bindings::cef_browser_host_t;
+use super::client::{self, Client};
+
+/// Evaluate JavaScript in the main frame using the DevTools `Runtime.evaluate`
+/// method, waiting for promises and resolving to the JSON-serialized result.
+/// The method is sent right away, so this must be called on the UI thread,
+/// but the returned future can be awaited anywhere
+pub unsafe fn evaluate(
+    client: *mut Client,
+    host: *mut cef_browser_host_t,
+    code: &str,
+) -> impl Future<Output = Result<Value, Box<dyn std::error::Error + Send + Sync>>> {
+    let result = client::execute_dev_tools_method(
+        client,
+        host,
+        "Runtime.evaluate",
+        json!({
+            "expression": code,
+            "returnByValue": true,
+            "awaitPromise": true,
+        }),
+    );
+    async move {
+        let mut result = result.await?;
+        if let Some(exception) = result.get("exceptionDetails") {
+            let message = exception["exception"]["description"]
+                .as_str()
+                .or_else(|| exception["text"].as_str())
+                .unwrap_or("unknown exception");
+            return Err(Box::from(format!("script threw: {}", message)));
+        }
+        // `undefined` has no value
+        Ok(result["result"]
+            .get_mut("value")
+            .map(Value::take)
+            .unwrap_or(Value::Null))
+    }
+}
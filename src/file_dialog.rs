@@ -0,0 +1,453 @@
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use super::bindings::{
+    cef_file_dialog_callback_t, cef_file_dialog_mode_t, cef_file_dialog_mode_t_FILE_DIALOG_OPEN,
+    cef_file_dialog_mode_t_FILE_DIALOG_OPEN_FOLDER,
+    cef_file_dialog_mode_t_FILE_DIALOG_OPEN_MULTIPLE,
+    cef_file_dialog_mode_t_FILE_DIALOG_OVERWRITEPROMPT_FLAG,
+    cef_file_dialog_mode_t_FILE_DIALOG_SAVE, cef_file_dialog_mode_t_FILE_DIALOG_TYPE_MASK,
+    cef_string_list_alloc, cef_string_list_append, cef_string_list_free, cef_string_utf16_clear,
+};
+use super::value::to_cef_string;
+
+/// What a file dialog picks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileDialogMode {
+    /// A single existing file
+    Open,
+    /// Any number of existing files
+    OpenMultiple,
+    /// An existing folder
+    OpenFolder,
+    /// A file to save to, which may not exist yet
+    Save,
+}
+
+impl FileDialogMode {
+    pub(crate) fn to_cef(self) -> cef_file_dialog_mode_t {
+        match self {
+            FileDialogMode::Open => cef_file_dialog_mode_t_FILE_DIALOG_OPEN,
+            FileDialogMode::OpenMultiple => cef_file_dialog_mode_t_FILE_DIALOG_OPEN_MULTIPLE,
+            FileDialogMode::OpenFolder => cef_file_dialog_mode_t_FILE_DIALOG_OPEN_FOLDER,
+            FileDialogMode::Save => cef_file_dialog_mode_t_FILE_DIALOG_SAVE,
+        }
+    }
+
+    /// Read the mode from CEF's, ignoring any flags
+    pub(crate) fn from_cef(mode: cef_file_dialog_mode_t) -> FileDialogMode {
+        match mode & cef_file_dialog_mode_t_FILE_DIALOG_TYPE_MASK {
+            m if m == cef_file_dialog_mode_t_FILE_DIALOG_OPEN_MULTIPLE => {
+                FileDialogMode::OpenMultiple
+            }
+            m if m == cef_file_dialog_mode_t_FILE_DIALOG_OPEN_FOLDER => FileDialogMode::OpenFolder,
+            m if m == cef_file_dialog_mode_t_FILE_DIALOG_SAVE => FileDialogMode::Save,
+            _ => FileDialogMode::Open,
+        }
+    }
+}
+
+/// A filter for which files a dialog shows, such as `Images|.png;.jpg`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileFilter {
+    /// What to call the filter, may be empty
+    pub description: String,
+    /// Extensions including the dot such as `.png`, or MIME types such as
+    /// `image/*`
+    pub extensions: Vec<String>,
+}
+
+impl FileFilter {
+    pub fn new(description: &str, extensions: &[&str]) -> FileFilter {
+        FileFilter {
+            description: description.to_owned(),
+            extensions: extensions.iter().map(|e| (*e).to_owned()).collect(),
+        }
+    }
+
+    /// Parse CEF's filter format: `Description|.ext1;.ext2`, or just `.ext`
+    /// or `mime/type`
+    pub fn parse(filter: &str) -> FileFilter {
+        let (description, extensions) = match filter.find('|') {
+            Some(i) => (&filter[..i], &filter[i + 1..]),
+            None => ("", filter),
+        };
+        FileFilter {
+            description: description.to_owned(),
+            extensions: extensions
+                .split(';')
+                .map(str::trim)
+                .filter(|e| !e.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        }
+    }
+
+    /// Format in CEF's filter format
+    pub(crate) fn to_cef_format(&self) -> String {
+        if self.description.is_empty() && self.extensions.len() == 1 {
+            self.extensions[0].clone()
+        } else {
+            format!("{}|{}", self.description, self.extensions.join(";"))
+        }
+    }
+}
+
+/// Options for `Browser::run_file_dialog`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDialogOptions {
+    pub mode: FileDialogMode,
+    /// The dialog's title, empty for the default one
+    pub title: String,
+    /// The folder or file name to start from
+    pub default_path: Option<PathBuf>,
+    pub filters: Vec<FileFilter>,
+    /// The index of the filter selected to start with, within `filters`;
+    /// 0 when there are none
+    pub selected_filter: usize,
+    /// Ask before overwriting an existing file, only used when saving
+    pub overwrite_prompt: bool,
+}
+
+impl Default for FileDialogOptions {
+    fn default() -> FileDialogOptions {
+        FileDialogOptions {
+            mode: FileDialogMode::Open,
+            title: String::new(),
+            default_path: None,
+            filters: Vec::new(),
+            selected_filter: 0,
+            overwrite_prompt: true,
+        }
+    }
+}
+
+impl FileDialogOptions {
+    /// The mode, including flags, to hand to CEF
+    pub(crate) fn cef_mode(&self) -> cef_file_dialog_mode_t {
+        let mut mode = self.mode.to_cef();
+        if self.mode == FileDialogMode::Save && self.overwrite_prompt {
+            mode |= cef_file_dialog_mode_t_FILE_DIALOG_OVERWRITEPROMPT_FLAG;
+        }
+        mode
+    }
+
+    /// Read the options given to `cef.openFileDialog(options)`, i.e.
+    ///
+    /// ```json
+    /// {
+    ///     "mode": "open" | "openMultiple" | "openFolder" | "save",
+    ///     "title": "Pick a picture",
+    ///     "defaultPath": "holiday.png",
+    ///     "filters": ["Images|.png;.jpg", { "description": "Text", "extensions": [".txt"] }],
+    ///     "selectedFilter": 0,
+    ///     "overwritePrompt": true
+    /// }
+    /// ```
+    ///
+    /// Anything left out keeps its default. A `selectedFilter` that isn't
+    /// one of the `filters` is an error
+    pub fn from_json(options: &Value) -> Result<FileDialogOptions, String> {
+        let mut dialog = FileDialogOptions::default();
+        let mut selected_filter = None;
+        let options = match options {
+            Value::Null => return Ok(dialog),
+            Value::Object(options) => options,
+            _ => return Err("options must be an object".to_owned()),
+        };
+
+        for (key, value) in options.iter() {
+            match key.as_str() {
+                "mode" => {
+                    dialog.mode = match value.as_str() {
+                        Some("open") => FileDialogMode::Open,
+                        Some("openMultiple") => FileDialogMode::OpenMultiple,
+                        Some("openFolder") => FileDialogMode::OpenFolder,
+                        Some("save") => FileDialogMode::Save,
+                        _ => return Err(format!("unknown mode `{}`", value)),
+                    }
+                }
+                "title" => dialog.title = value.as_str().unwrap_or("").to_owned(),
+                "defaultPath" => {
+                    dialog.default_path =
+                        value.as_str().filter(|p| !p.is_empty()).map(PathBuf::from)
+                }
+                "filters" => {
+                    let filters = value.as_array().ok_or("`filters` must be an array")?;
+                    dialog.filters = filters
+                        .iter()
+                        .map(|filter| match filter {
+                            Value::String(filter) => Ok(FileFilter::parse(filter)),
+                            Value::Object(_) => Ok(FileFilter {
+                                description: filter["description"]
+                                    .as_str()
+                                    .unwrap_or("")
+                                    .to_owned(),
+                                extensions: filter["extensions"]
+                                    .as_array()
+                                    .map(|e| {
+                                        e.iter()
+                                            .filter_map(Value::as_str)
+                                            .map(str::to_owned)
+                                            .collect()
+                                    })
+                                    .unwrap_or_default(),
+                            }),
+                            _ => Err("filters must be strings or objects".to_owned()),
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                "selectedFilter" => {
+                    selected_filter = Some(
+                        value
+                            .as_u64()
+                            .ok_or("`selectedFilter` must be a positive integer")?
+                            as usize,
+                    )
+                }
+                "overwritePrompt" => {
+                    dialog.overwrite_prompt = value
+                        .as_bool()
+                        .ok_or("`overwritePrompt` must be a boolean")?
+                }
+                _ => return Err(format!("unknown option `{}`", key)),
+            }
+        }
+        if let Some(selected_filter) = selected_filter {
+            dialog.selected_filter = check_selected_filter(selected_filter as i64, dialog.filters.len())?;
+        }
+        Ok(dialog)
+    }
+}
+
+/// Check that a selected filter index is one of `filters`, or 0 when there
+/// are none, the same for dialogs we run and those CEF asks us to answer
+pub(crate) fn check_selected_filter(selected_filter: i64, filters: usize) -> Result<usize, String> {
+    if selected_filter == 0 || (selected_filter > 0 && (selected_filter as usize) < filters) {
+        Ok(selected_filter as usize)
+    } else {
+        Err(format!(
+            "selected filter {} is out of range for {} filter(s)",
+            selected_filter, filters
+        ))
+    }
+}
+
+/// A page asking for a file dialog, i.e. through `<input type="file">`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDialogRequest {
+    pub browser_id: i32,
+    /// What the page asked for; its filters come from the input's `accept`
+    /// attribute
+    pub options: FileDialogOptions,
+}
+
+/// The answer to a `FileDialogRequest`. Dropping the callback without
+/// answering cancels the dialog
+pub struct FileDialogCallback {
+    callback: *mut cef_file_dialog_callback_t,
+    selected_filter: usize,
+}
+
+impl FileDialogCallback {
+    /// Answer with the chosen paths
+    pub fn cont(mut self, paths: &[PathBuf]) {
+        unsafe {
+            let list = cef_string_list_alloc();
+            for path in paths.iter() {
+                let mut cef_path = to_cef_string(&path.display().to_string());
+                cef_string_list_append(list, &cef_path);
+                cef_string_utf16_clear(&mut cef_path);
+            }
+            (*self.callback).cont.expect("cont is a function")(
+                self.callback,
+                self.selected_filter as i32,
+                list,
+            );
+            cef_string_list_free(list);
+        }
+        self.finish();
+    }
+
+    /// Choose nothing, as if the user closed the dialog
+    pub fn cancel(mut self) {
+        unsafe {
+            (*self.callback).cancel.expect("cancel is a function")(self.callback);
+        }
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        unsafe {
+            (*self.callback)
+                .base
+                .release
+                .expect("release is a function")(&mut (*self.callback).base);
+        }
+        self.callback = std::ptr::null_mut();
+    }
+}
+
+impl Drop for FileDialogCallback {
+    fn drop(&mut self) {
+        if !self.callback.is_null() {
+            unsafe {
+                (*self.callback).cancel.expect("cancel is a function")(self.callback);
+            }
+            self.finish();
+        }
+    }
+}
+
+/// Answers file dialogs instead of Chromium's built-in one. The callback can
+/// be answered asynchronously, but must be answered on the UI thread
+pub trait FileDialogHandler {
+    /// Whether to answer this request, rather than letting Chromium show its
+    /// own dialog
+    fn should_handle(&mut self, request: &FileDialogRequest) -> bool {
+        let _ = request;
+        true
+    }
+
+    fn run_file_dialog(&mut self, request: &FileDialogRequest, callback: FileDialogCallback);
+}
+
+/// Answer immediately with the returned paths, or cancel on `None`; handy
+/// for scripting dialogs in tests
+impl<F: FnMut(&FileDialogRequest) -> Option<Vec<PathBuf>>> FileDialogHandler for F {
+    fn run_file_dialog(&mut self, request: &FileDialogRequest, callback: FileDialogCallback) {
+        match self(request) {
+            Some(paths) => callback.cont(&paths),
+            None => callback.cancel(),
+        }
+    }
+}
+
+/// Hand a file dialog request over to the handler, returning whether it
+/// took it
+pub unsafe fn run_file_dialog(
+    handler: &mut dyn FileDialogHandler,
+    request: FileDialogRequest,
+    callback: *mut cef_file_dialog_callback_t,
+) -> bool {
+    if !handler.should_handle(&request) {
+        return false;
+    }
+    (*callback).base.add_ref.expect("add_ref is a function")(&mut (*callback).base);
+    let selected_filter = request.options.selected_filter;
+    handler.run_file_dialog(
+        &request,
+        FileDialogCallback {
+            callback,
+            selected_filter,
+        },
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use super::{FileDialogMode, FileDialogOptions, FileFilter};
+    use crate::bindings::{
+        cef_file_dialog_mode_t_FILE_DIALOG_OPEN, cef_file_dialog_mode_t_FILE_DIALOG_OPEN_FOLDER,
+        cef_file_dialog_mode_t_FILE_DIALOG_OPEN_MULTIPLE,
+        cef_file_dialog_mode_t_FILE_DIALOG_OVERWRITEPROMPT_FLAG,
+        cef_file_dialog_mode_t_FILE_DIALOG_SAVE,
+    };
+
+    #[test]
+    fn filters_round_trip() {
+        for filter in &["Images|.png;.jpg", ".txt", "image/*", "|.a;.b", "Text|.txt"] {
+            assert_eq!(FileFilter::parse(filter).to_cef_format(), *filter);
+        }
+    }
+
+    #[test]
+    fn filters_parse() {
+        assert_eq!(FileFilter::parse("Images|.png; .jpg;"), FileFilter::new("Images", &[".png", ".jpg"]));
+        assert_eq!(FileFilter::parse(".txt"), FileFilter::new("", &[".txt"]));
+        assert_eq!(FileFilter::parse("Nothing|"), FileFilter::new("Nothing", &[]));
+    }
+
+    #[test]
+    fn mode_from_cef_ignores_flags() {
+        let flag = cef_file_dialog_mode_t_FILE_DIALOG_OVERWRITEPROMPT_FLAG;
+        assert_eq!(FileDialogMode::from_cef(cef_file_dialog_mode_t_FILE_DIALOG_SAVE | flag), FileDialogMode::Save);
+        assert_eq!(FileDialogMode::from_cef(cef_file_dialog_mode_t_FILE_DIALOG_OPEN | flag), FileDialogMode::Open);
+        assert_eq!(
+            FileDialogMode::from_cef(cef_file_dialog_mode_t_FILE_DIALOG_OPEN_MULTIPLE | flag),
+            FileDialogMode::OpenMultiple
+        );
+        assert_eq!(
+            FileDialogMode::from_cef(cef_file_dialog_mode_t_FILE_DIALOG_OPEN_FOLDER),
+            FileDialogMode::OpenFolder
+        );
+    }
+
+    #[test]
+    fn mode_round_trips_through_cef() {
+        for mode in &[
+            FileDialogMode::Open,
+            FileDialogMode::OpenMultiple,
+            FileDialogMode::OpenFolder,
+            FileDialogMode::Save,
+        ] {
+            assert_eq!(FileDialogMode::from_cef(mode.to_cef()), *mode);
+        }
+    }
+
+    #[test]
+    fn options_from_json() {
+        let options = FileDialogOptions::from_json(&json!({
+            "mode": "save",
+            "title": "Save as",
+            "defaultPath": "out.txt",
+            "filters": ["Text|.txt", { "description": "Logs", "extensions": [".log"] }],
+            "selectedFilter": 1,
+            "overwritePrompt": false,
+        }))
+        .unwrap();
+        assert_eq!(
+            options,
+            FileDialogOptions {
+                mode: FileDialogMode::Save,
+                title: "Save as".to_owned(),
+                default_path: Some(PathBuf::from("out.txt")),
+                filters: vec![FileFilter::new("Text", &[".txt"]), FileFilter::new("Logs", &[".log"])],
+                selected_filter: 1,
+                overwrite_prompt: false,
+            }
+        );
+    }
+
+    #[test]
+    fn options_from_json_defaults() {
+        assert_eq!(FileDialogOptions::from_json(&json!(null)).unwrap(), FileDialogOptions::default());
+        assert_eq!(FileDialogOptions::from_json(&json!({})).unwrap(), FileDialogOptions::default());
+        let options = FileDialogOptions::from_json(&json!({ "defaultPath": "" })).unwrap();
+        assert_eq!(options.default_path, None);
+    }
+
+    #[test]
+    fn options_from_json_rejects_bad_options() {
+        assert!(FileDialogOptions::from_json(&json!("open")).is_err());
+        assert!(FileDialogOptions::from_json(&json!({ "mode": "delete" })).is_err());
+        assert!(FileDialogOptions::from_json(&json!({ "colour": "red" })).is_err());
+        assert!(FileDialogOptions::from_json(&json!({ "filters": [1] })).is_err());
+        assert!(FileDialogOptions::from_json(&json!({ "selectedFilter": -1, "filters": [".txt"] })).is_err());
+    }
+
+    #[test]
+    fn options_from_json_rejects_out_of_range_filter() {
+        assert!(FileDialogOptions::from_json(&json!({ "selectedFilter": 1 })).is_err());
+        assert!(FileDialogOptions::from_json(&json!({ "selectedFilter": 0 })).is_ok());
+        assert!(FileDialogOptions::from_json(&json!({ "selectedFilter": 2, "filters": [".a", ".b"] })).is_err());
+        let options = FileDialogOptions::from_json(&json!({ "selectedFilter": 1, "filters": [".a", ".b"] })).unwrap();
+        assert_eq!(options.selected_filter, 1);
+    }
+}
@@ -0,0 +1,149 @@
+use std::mem::size_of;
+use std::os::raw::{c_int};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::bindings::{
+    cef_base_ref_counted_t, cef_browser_t, cef_focus_handler_t, cef_focus_source_t,
+    cef_focus_source_t_FOCUS_SOURCE_NAVIGATION,
+};
+
+/// What caused the browser to ask for focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusSource {
+    /// The browser is navigating, i.e. focusing an autofocus element
+    Navigation,
+    /// The user or the system focused the browser, i.e. by clicking on it
+    System,
+}
+
+#[repr(C)]
+pub struct FocusHandler {
+    focus_handler: cef_focus_handler_t,
+    ref_count: AtomicUsize,
+    take_focus: Option<Box<dyn FnMut(bool)>>,
+    focus_requested: Option<Box<dyn FnMut(FocusSource) -> bool>>,
+    got_focus: Option<Box<dyn FnMut()>>,
+}
+
+impl FocusHandler {
+    pub fn inc_ref(&self) {
+        self.ref_count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+unsafe extern "C" fn on_take_focus(
+    slf: *mut cef_focus_handler_t,
+    _browser: *mut cef_browser_t,
+    next: c_int,
+) {
+    let handler = slf as *mut FocusHandler;
+    if let Some(take_focus) = &mut (*handler).take_focus {
+        take_focus(next == 1);
+    }
+}
+
+unsafe extern "C" fn on_set_focus(
+    slf: *mut cef_focus_handler_t,
+    _browser: *mut cef_browser_t,
+    source: cef_focus_source_t,
+) -> c_int {
+    let handler = slf as *mut FocusHandler;
+    if let Some(focus_requested) = &mut (*handler).focus_requested {
+        let source = if source == cef_focus_source_t_FOCUS_SOURCE_NAVIGATION {
+            FocusSource::Navigation
+        } else {
+            FocusSource::System
+        };
+        // returning 1 cancels setting the focus
+        if focus_requested(source) {
+            0
+        } else {
+            1
+        }
+    } else {
+        0
+    }
+}
+
+unsafe extern "C" fn on_got_focus(slf: *mut cef_focus_handler_t, _browser: *mut cef_browser_t) {
+    let handler = slf as *mut FocusHandler;
+    if let Some(got_focus) = &mut (*handler).got_focus {
+        got_focus();
+    }
+}
+
+pub fn allocate() -> *mut FocusHandler {
+    let handler = FocusHandler {
+        focus_handler: cef_focus_handler_t {
+            base: cef_base_ref_counted_t {
+                size: size_of::<FocusHandler>() as u64,
+                add_ref: Some(add_ref),
+                release: Some(release),
+                has_one_ref: Some(has_one_ref),
+                has_at_least_one_ref: Some(has_at_least_one_ref),
+            },
+            on_take_focus: Some(on_take_focus),
+            on_set_focus: Some(on_set_focus),
+            on_got_focus: Some(on_got_focus),
+        },
+        ref_count: AtomicUsize::new(1),
+        take_focus: None,
+        focus_requested: None,
+        got_focus: None,
+    };
+
+    super::ref_counting::allocated("FocusHandler");
+    Box::into_raw(Box::from(handler))
+}
+
+pub unsafe fn set_take_focus_listener<F: FnMut(bool) + 'static>(slf: *mut FocusHandler, listener: F) {
+    (*slf).take_focus = Some(Box::from(listener));
+}
+
+pub unsafe fn set_focus_requested_listener<F: FnMut(FocusSource) -> bool + 'static>(slf: *mut FocusHandler, listener: F) {
+    (*slf).focus_requested = Some(Box::from(listener));
+}
+
+pub unsafe fn set_got_focus_listener<F: FnMut() + 'static>(slf: *mut FocusHandler, listener: F) {
+    (*slf).got_focus = Some(Box::from(listener));
+}
+
+extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
+    let focus_handler = base as *mut FocusHandler;
+    unsafe { (*focus_handler).ref_count.fetch_add(1, Ordering::SeqCst) };
+}
+
+extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
+    let focus_handler = base as *mut FocusHandler;
+    let count = unsafe { (*focus_handler).ref_count.fetch_sub(1, Ordering::SeqCst) - 1 };
+
+    if count == 0 {
+        unsafe {
+            Box::from_raw(focus_handler);
+            super::ref_counting::freed("FocusHandler");
+        }
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let focus_handler = base as *mut FocusHandler;
+    let count = unsafe { (*focus_handler).ref_count.load(Ordering::SeqCst) };
+    if count == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_at_least_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let focus_handler = base as *mut FocusHandler;
+    let count = unsafe { (*focus_handler).ref_count.load(Ordering::SeqCst) };
+    if count >= 1 {
+        1
+    } else {
+        0
+    }
+}
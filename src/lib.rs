@@ -1,57 +1,145 @@
 mod app;
 mod bindings;
+mod browser_handle;
 mod browser_process_handler;
+mod browser_registry;
+mod certificate_policy;
 mod client;
+mod completion_callback;
 mod context_menu_handler;
+mod cookie_manager;
+mod cookie_visitor;
+mod crash_recovery;
+mod credential_provider;
+mod delete_cookies_callback;
+mod dev_tools;
+mod dev_tools_pdf;
+mod dialog_handler;
 mod display_handler;
+mod drag_handler;
+mod eval;
+mod file_dialog;
+mod focus_handler;
 mod life_span_handler;
+mod request_context;
 mod request_handler;
 mod render_process_handler;
 mod schedule;
+mod screenshot;
+mod set_cookie_callback;
+mod task;
 mod v8_pdf_print_handler;
 mod v8_file_dialog_handler;
+mod v8_drag_drop_handler;
+mod preferences;
+mod pdf_print_settings;
+mod print_backend;
+mod print_handler;
 mod print_pdf_callback;
+mod raw_window;
+mod pump;
+mod ref_counting;
 mod run_file_dialog_callback;
+mod value;
+mod wake_handle;
+#[cfg(feature = "winit")]
+mod winit_integration;
 
 /// An actual browser within the CEF system
-#[cfg(windows)]
-#[path = "browser_windows.rs"]
 mod browser;
 
 pub use browser::Browser;
+pub use browser_handle::BrowserHandle;
+pub use certificate_policy::{CertificateDecision, CertificateError, CertificateInfo, CertificatePolicy};
+pub use cookie_manager::{Cookie, CookieManager, SameSite};
+pub use crash_recovery::{CrashRecovery, RecoveryAction, RenderProcessTerminated, TerminationStatus};
+pub use credential_provider::{AuthCallback, AuthRequest, CredentialProvider, Credentials, InMemoryCredentials};
+pub use dev_tools_pdf::DevToolsPdfOptions;
+pub use drag_handler::{DragData, DragEnter, DragOperations, DragPolicy};
+pub use file_dialog::{
+    FileDialogCallback, FileDialogHandler, FileDialogMode, FileDialogOptions, FileDialogRequest, FileFilter,
+};
+pub use focus_handler::FocusSource;
+pub use life_span_handler::BrowserClosed;
+pub use pdf_print_settings::{Margins, PaperSize, PdfPrintSettings};
+pub use print_backend::{Duplex, LpBackend, PdfFileBackend, PrintBackend, PrintSettings};
+pub use preferences::{ProxySettings, WebRtcIpHandlingPolicy};
+pub use request_context::{RequestContext, RequestContextBuilder};
+pub use raw_window::RawWindow;
+pub use ref_counting::live_objects;
+pub use schedule::PumpWaker;
+pub use screenshot::{ClipRect, ScreenshotFormat, ScreenshotMode, ScreenshotOptions};
+pub use task::Thread;
+pub use wake_handle::WakeHandle;
 
 use std::mem::size_of;
 use std::ptr::null_mut;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use bindings::{
     cef_app_t, cef_execute_process, cef_initialize, cef_log_severity_t_LOGSEVERITY_ERROR,
     cef_log_severity_t_LOGSEVERITY_INFO, cef_main_args_t, cef_settings_t, cef_shutdown,
-    cef_enable_highdpi_support,
 };
 
+/// How the CEF message loop gets run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageLoopMode {
+    /// The host runs its own loop and calls `do_message_loop_work` whenever
+    /// `should_do_work` says so
+    ExternalPump,
+    /// CEF runs its UI thread on a thread of its own; the host's main thread
+    /// is free, but browsers must only be touched from tasks posted to
+    /// `Thread::UI`
+    MultiThreaded,
+    /// CEF's own loop, run on the main thread by `Cef::run`
+    RunMessageLoop,
+}
+
 /// The CEF system, including scheduler
 pub struct Cef {
+    mode: MessageLoopMode,
     schedule: Arc<schedule::Schedule>,
+    registry: Arc<browser_registry::BrowserRegistry>,
     _app: *mut app::App,
 }
 
+/// How long shutting down waits for browsers to close before giving up
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 impl Cef {
     /// Initialize the CEF context and deal with forked processes. This should 
     /// generally be called as soon as possible in your application's lifetime
-    #[cfg(windows)]
     pub fn initialize(
         debug_port: Option<u16>,
         enable_command_line_args: bool,
+        mode: MessageLoopMode,
     ) -> Result<Cef, Box<dyn std::error::Error>> {
         // collect our args
+        #[cfg(windows)]
         let main_args = unsafe {
             cef_main_args_t {
                 instance: winapi::um::libloaderapi::GetModuleHandleA(null_mut())
                     as bindings::HINSTANCE,
             }
         };
+        #[cfg(target_os = "linux")]
+        let main_args = {
+            use std::ffi::CString;
+            use std::os::unix::ffi::OsStrExt;
+
+            // CEF holds on to the arguments for as long as the process runs
+            let argv: Vec<*mut std::os::raw::c_char> = std::env::args_os()
+                .map(|arg| CString::new(arg.as_bytes()).expect("arguments don't contain nul bytes").into_raw())
+                .collect();
+            let argv = Box::leak(argv.into_boxed_slice());
+            cef_main_args_t {
+                argc: argv.len() as std::os::raw::c_int,
+                argv: argv.as_mut_ptr(),
+            }
+        };
     
-        unsafe { cef_enable_highdpi_support() };
+        #[cfg(windows)]
+        unsafe { bindings::cef_enable_highdpi_support() };
     
         log::debug!("preparing app");
         let schedule = Arc::new(schedule::Schedule::new());
@@ -72,8 +160,8 @@ impl Cef {
             settings.remote_debugging_port = port as i32;
         }
         settings.command_line_args_disabled = if enable_command_line_args { 0 } else { 1 };
-        settings.multi_threaded_message_loop = 0;
-        settings.external_message_pump = 1;
+        settings.multi_threaded_message_loop = (mode == MessageLoopMode::MultiThreaded) as i32;
+        settings.external_message_pump = (mode == MessageLoopMode::ExternalPump) as i32;
         if cfg!(debug_assertions) {
             settings.log_severity = cef_log_severity_t_LOGSEVERITY_INFO;
         } else {
@@ -89,26 +177,153 @@ impl Cef {
         }
     
         Ok(Cef {
+            mode,
             schedule,
+            registry: Arc::new(browser_registry::BrowserRegistry::new()),
             _app: app,
         })
     }
     
+    pub fn message_loop_mode(&self) -> MessageLoopMode {
+        self.mode
+    }
+
     /// Tell CEF to do its thing
     pub fn do_message_loop_work(&self) {
+        self.schedule.work_started();
         unsafe { bindings::cef_do_message_loop_work(); }
     }
 
-    /// Check whether or not the CEF scheduler is looking to process data
+    /// How long until CEF wants `do_message_loop_work` to be called, `None`
+    /// if it hasn't asked
+    pub fn time_until_work(&self) -> Option<Duration> {
+        self.schedule.time_until_work()
+    }
+
+    /// Block until CEF wants `do_message_loop_work` to be called, a
+    /// `PumpWaker` is woken, or `timeout` passes. Returns whether work is
+    /// due. This doesn't wake up for window messages; see `wake_handle` for
+    /// waiting on those at the same time
+    pub fn wait_for_work(&self, timeout: Option<Duration>) -> bool {
+        self.schedule.wait_for_work(timeout)
+    }
+
+    /// A handle to wait on with an existing event loop, i.e. with epoll or
+    /// `MsgWaitForMultipleObjects`
+    pub fn wake_handle(&self) -> &WakeHandle {
+        self.schedule.wake_handle()
+    }
+
+    /// Get a waker that other threads can use to interrupt waiting for work
+    pub fn waker(&self) -> PumpWaker {
+        PumpWaker::new(self.schedule.clone())
+    }
+
+    /// Call `callback` whenever CEF schedules work or a `PumpWaker` is woken,
+    /// i.e. to wake up another event loop. It's called from whichever thread
+    /// CEF or the waker is on
+    pub fn set_wake_callback<F: Fn() + Send + Sync + 'static>(&self, callback: F) {
+        self.schedule.set_wake_callback(Box::from(callback));
+    }
+
+    /// Check whether or not the CEF scheduler is looking to process data.
+    /// Only meaningful with `MessageLoopMode::ExternalPump`
     pub fn should_do_work(&self) -> bool {
-        self.schedule.should_do_work()
+        self.mode == MessageLoopMode::ExternalPump && self.schedule.should_do_work()
+    }
+
+    /// Run `task` on the given CEF thread. This can be called from any thread
+    /// once CEF has been initialized; returns `false` if the task couldn't be
+    /// posted
+    pub fn post_task<F: FnOnce() + Send + 'static>(thread: Thread, task: F) -> bool {
+        task::post(thread, Box::from(task))
+    }
+
+    /// Run `task` on the given CEF thread once `delay` has passed
+    pub fn post_delayed_task<F: FnOnce() + Send + 'static>(thread: Thread, delay: Duration, task: F) -> bool {
+        task::post_delayed(thread, delay, Box::from(task))
+    }
+
+    /// Whether the calling thread is the given CEF thread
+    pub fn currently_on(thread: Thread) -> bool {
+        task::currently_on(thread)
+    }
+
+    /// Use `backend` for `window.print()` and `Browser::print`. CEF only
+    /// consults this on Linux, where printing does nothing without one;
+    /// elsewhere the system print dialog is used. The backend is called on
+    /// the UI thread
+    pub fn set_print_backend<B: PrintBackend + Send + 'static>(&mut self, backend: B) {
+        unsafe { app::set_print_backend(self._app, Box::from(backend)) };
+    }
+
+    /// Close every browser and shut CEF down, the same as dropping it
+    pub fn shutdown(self) {
+        drop(self);
+    }
+
+    /// Force every browser closed, then keep pumping messages until they
+    /// have all been destroyed or we time out
+    fn close_all_browsers(&self) {
+        if self.registry.is_empty() {
+            return;
+        }
+        log::debug!("closing {} browser(s)..", self.registry.len());
+        unsafe { self.registry.close_all(true) };
+
+        let start = Instant::now();
+        while !self.registry.is_empty() {
+            if start.elapsed() > SHUTDOWN_TIMEOUT {
+                log::warn!(
+                    "{} browser(s) still alive after {:?}, shutting down anyway",
+                    self.registry.len(),
+                    SHUTDOWN_TIMEOUT
+                );
+                break;
+            }
+
+            if self.mode == MessageLoopMode::MultiThreaded {
+                // CEF closes them on its own thread
+                std::thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+
+            // windowed browsers need their host windows to process the close
+            #[cfg(windows)]
+            unsafe {
+                use winapi::um::winuser::{DispatchMessageW, PeekMessageW, TranslateMessage, PM_REMOVE};
+                let mut msg = std::mem::MaybeUninit::uninit();
+                while PeekMessageW(msg.as_mut_ptr(), null_mut(), 0, 0, PM_REMOVE) != 0 {
+                    let msg = msg.assume_init();
+                    TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+            }
+            self.do_message_loop_work();
+            std::thread::sleep(Duration::from_millis(1));
+        }
     }
 }
 
 impl Drop for Cef {
     fn drop(&mut self) {
+        self.close_all_browsers();
         log::debug!("shutting down CEF..");
-        unsafe { cef_shutdown() };
+        unsafe {
+            cef_shutdown();
+            ref_counting::release(self._app);
+        }
         log::debug!("CEF shutdown!");
+        ref_counting::report();
+        #[cfg(feature = "leak-tracking")]
+        {
+            if !std::thread::panicking() {
+                debug_assert!(
+                    live_objects().is_empty(),
+                    "CEF objects leaked past shutdown: {:?}",
+                    live_objects()
+                );
+            }
+        }
     }
 }
@@ -1,15 +1,30 @@
+use std::collections::{HashMap, HashSet};
 use std::mem::size_of;
 use std::os::raw::{c_int};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures::channel::oneshot;
 
 use super::bindings::{
     cef_base_ref_counted_t, cef_browser_t, cef_life_span_handler_t,
 };
+use super::browser_registry::{browser_id, BrowserRegistry};
+
+/// Sent once a browser has been closed and destroyed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrowserClosed {
+    pub browser_id: i32,
+}
 
 #[repr(C)]
 pub struct LifeSpanHandler {
     life_span_handler: cef_life_span_handler_t,
     ref_count: AtomicUsize,
+    registry: Arc<BrowserRegistry>,
+    closing: HashSet<i32>,
+    close_waiters: HashMap<i32, Vec<oneshot::Sender<()>>>,
+    closed: Option<Box<dyn FnMut(&BrowserClosed)>>,
 }
 
 impl LifeSpanHandler {
@@ -18,14 +33,45 @@ impl LifeSpanHandler {
     }
 }
 
-extern "C" fn do_close(_slf: *mut cef_life_span_handler_t, _browser: *mut cef_browser_t) -> c_int {
+unsafe extern "C" fn on_after_created(slf: *mut cef_life_span_handler_t, browser: *mut cef_browser_t) {
+    let handler = slf as *mut LifeSpanHandler;
+    log::debug!("browser {} created", browser_id(browser));
+    (*handler).registry.insert(browser);
+    (*handler).registry.notify_created(browser);
+}
+
+unsafe extern "C" fn do_close(slf: *mut cef_life_span_handler_t, browser: *mut cef_browser_t) -> c_int {
+    let handler = slf as *mut LifeSpanHandler;
+    let id = browser_id(browser);
+    log::debug!("browser {} is closing", id);
+    (*handler).closing.insert(id);
+
+    // let the close go ahead; for windowed browsers CEF now sends WM_CLOSE to
+    // the top-level window, which should call `try_close` again and destroy
+    // itself once that returns `true`
     0
 }
 
-unsafe extern "C" fn on_before_close(_slf: *mut cef_life_span_handler_t, _browser: *mut cef_browser_t) {
+unsafe extern "C" fn on_before_close(slf: *mut cef_life_span_handler_t, browser: *mut cef_browser_t) {
+    let handler = slf as *mut LifeSpanHandler;
+    let id = browser_id(browser);
+    log::debug!("browser {} closed", id);
+
+    (*handler).closing.remove(&id);
+    (*handler).registry.remove(browser);
+    if let Some(waiters) = (*handler).close_waiters.remove(&id) {
+        for waiter in waiters {
+            let _ = waiter.send(());
+        }
+    }
+    let event = BrowserClosed { browser_id: id };
+    if let Some(closed) = &mut (*handler).closed {
+        closed(&event);
+    }
+    (*handler).registry.notify_destroyed(&event);
 }
 
-pub fn allocate() -> *mut LifeSpanHandler {
+pub fn allocate(registry: Arc<BrowserRegistry>) -> *mut LifeSpanHandler {
     let handler = LifeSpanHandler {
         life_span_handler: cef_life_span_handler_t {
             base: cef_base_ref_counted_t {
@@ -36,16 +82,41 @@ pub fn allocate() -> *mut LifeSpanHandler {
                 has_at_least_one_ref: Some(has_at_least_one_ref),
             },
             on_before_popup: None,
-            on_after_created: None,
+            on_after_created: Some(on_after_created),
             do_close: Some(do_close),
             on_before_close: Some(on_before_close),
         },
         ref_count: AtomicUsize::new(1),
+        registry,
+        closing: HashSet::new(),
+        close_waiters: HashMap::new(),
+        closed: None,
     };
 
+    super::ref_counting::allocated("LifeSpanHandler");
     Box::into_raw(Box::from(handler))
 }
 
+/// Get a receiver that fires once the browser with the given id has closed.
+/// Returns `None` if that browser is already gone
+pub unsafe fn wait_for_close(slf: *mut LifeSpanHandler, id: i32) -> Option<oneshot::Receiver<()>> {
+    if !(*slf).registry.contains_id(id) {
+        return None;
+    }
+    let (sender, receiver) = oneshot::channel();
+    (*slf).close_waiters.entry(id).or_insert_with(Vec::new).push(sender);
+    Some(receiver)
+}
+
+/// Whether the browser with the given id has started closing
+pub unsafe fn is_closing(slf: *mut LifeSpanHandler, id: i32) -> bool {
+    (*slf).closing.contains(&id)
+}
+
+pub unsafe fn set_closed_listener<F: FnMut(&BrowserClosed) + 'static>(slf: *mut LifeSpanHandler, listener: F) {
+    (*slf).closed = Some(Box::from(listener));
+}
+
 extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
     let life_span_handler = base as *mut LifeSpanHandler;
     unsafe {
@@ -67,6 +138,7 @@ extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
     if count == 0 {
         unsafe {
             Box::from_raw(life_span_handler);
+            super::ref_counting::freed("LifeSpanHandler");
         }
         1
     } else {
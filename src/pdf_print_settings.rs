@@ -0,0 +1,310 @@
+use serde_json::Value;
+
+use super::bindings::{
+    _cef_pdf_print_settings_t, cef_pdf_print_margin_type_t_PDF_PRINT_MARGIN_CUSTOM,
+    cef_pdf_print_margin_type_t_PDF_PRINT_MARGIN_DEFAULT,
+    cef_pdf_print_margin_type_t_PDF_PRINT_MARGIN_MINIMUM,
+    cef_pdf_print_margin_type_t_PDF_PRINT_MARGIN_NONE, cef_string_t, cef_string_utf16_clear,
+};
+use super::value::to_cef_string;
+
+const MICRONS_PER_MM: f64 = 1000.0;
+const MICRONS_PER_INCH: f64 = 25400.0;
+
+/// The size of the paper to print on, in portrait orientation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaperSize {
+    /// 297 x 420 mm
+    A3,
+    /// 210 x 297 mm
+    A4,
+    /// 148 x 210 mm
+    A5,
+    /// 8.5 x 11 in
+    Letter,
+    /// 8.5 x 14 in
+    Legal,
+    /// A custom size in microns
+    Custom { width: i32, height: i32 },
+}
+
+impl PaperSize {
+    pub fn millimeters(width: f64, height: f64) -> PaperSize {
+        PaperSize::Custom {
+            width: (width * MICRONS_PER_MM).round() as i32,
+            height: (height * MICRONS_PER_MM).round() as i32,
+        }
+    }
+
+    pub fn inches(width: f64, height: f64) -> PaperSize {
+        PaperSize::Custom {
+            width: (width * MICRONS_PER_INCH).round() as i32,
+            height: (height * MICRONS_PER_INCH).round() as i32,
+        }
+    }
+
+    /// Width and height in microns
+    pub fn microns(&self) -> (i32, i32) {
+        match *self {
+            PaperSize::A3 => (297_000, 420_000),
+            PaperSize::A4 => (210_000, 297_000),
+            PaperSize::A5 => (148_000, 210_000),
+            PaperSize::Letter => (215_900, 279_400),
+            PaperSize::Legal => (215_900, 355_600),
+            PaperSize::Custom { width, height } => (width, height),
+        }
+    }
+}
+
+/// The page margins
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Margins {
+    /// Chrome's default margins, about 1 inch
+    Default,
+    None,
+    /// The smallest margins the printer supports
+    Minimum,
+    /// Custom margins in millimeters
+    Custom {
+        top: f64,
+        right: f64,
+        bottom: f64,
+        left: f64,
+    },
+}
+
+/// How to print a page to PDF
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfPrintSettings {
+    paper_size: PaperSize,
+    landscape: bool,
+    scale: f64,
+    margins: Margins,
+    header_footer: Option<(String, String)>,
+    selection_only: bool,
+    backgrounds: bool,
+}
+
+impl Default for PdfPrintSettings {
+    /// A4 portrait at 100%, default margins, no header or footer, with
+    /// backgrounds
+    fn default() -> PdfPrintSettings {
+        PdfPrintSettings {
+            paper_size: PaperSize::A4,
+            landscape: false,
+            scale: 1.0,
+            margins: Margins::Default,
+            header_footer: None,
+            selection_only: false,
+            backgrounds: true,
+        }
+    }
+}
+
+impl PdfPrintSettings {
+    pub fn new() -> PdfPrintSettings {
+        PdfPrintSettings::default()
+    }
+
+    pub fn paper_size(mut self, paper_size: PaperSize) -> PdfPrintSettings {
+        self.paper_size = paper_size;
+        self
+    }
+
+    pub fn landscape(mut self, landscape: bool) -> PdfPrintSettings {
+        self.landscape = landscape;
+        self
+    }
+
+    /// Scale the page, `1.0` being 100%
+    pub fn scale(mut self, scale: f64) -> PdfPrintSettings {
+        self.scale = scale;
+        self
+    }
+
+    pub fn margins(mut self, margins: Margins) -> PdfPrintSettings {
+        self.margins = margins;
+        self
+    }
+
+    /// Print a header and footer with the given title and URL
+    pub fn header_footer(mut self, title: &str, url: &str) -> PdfPrintSettings {
+        self.header_footer = Some((title.to_owned(), url.to_owned()));
+        self
+    }
+
+    /// Only print the current selection
+    pub fn selection_only(mut self, selection_only: bool) -> PdfPrintSettings {
+        self.selection_only = selection_only;
+        self
+    }
+
+    /// Print background colours and images
+    pub fn backgrounds(mut self, backgrounds: bool) -> PdfPrintSettings {
+        self.backgrounds = backgrounds;
+        self
+    }
+
+    /// Read the options given to `cef.printToPDF(path, options)`, i.e.
+    ///
+    /// ```json
+    /// {
+    ///     "paperSize": "Letter" | { "width": 100, "height": 150, "unit": "mm" | "in" },
+    ///     "landscape": true,
+    ///     "scale": 0.8,
+    ///     "margins": "default" | "none" | "minimum" | { "top": 10, "right": 10, "bottom": 10, "left": 10 },
+    ///     "headerFooter": { "title": "Invoice", "url": "https://example.com" },
+    ///     "selectionOnly": false,
+    ///     "backgrounds": true
+    /// }
+    /// ```
+    ///
+    /// Anything left out keeps its default
+    pub fn from_json(options: &Value) -> Result<PdfPrintSettings, String> {
+        let mut settings = PdfPrintSettings::default();
+        let options = match options {
+            Value::Null => return Ok(settings),
+            Value::Object(options) => options,
+            _ => return Err("options must be an object".to_owned()),
+        };
+
+        for (key, value) in options.iter() {
+            match key.as_str() {
+                "paperSize" => settings.paper_size = paper_size_from_json(value)?,
+                "landscape" => settings.landscape = bool_from_json(key, value)?,
+                "scale" => settings.scale = number_from_json(key, value)?,
+                "margins" => settings.margins = margins_from_json(value)?,
+                "headerFooter" => {
+                    let title = value["title"].as_str().unwrap_or("");
+                    let url = value["url"].as_str().unwrap_or("");
+                    settings.header_footer = Some((title.to_owned(), url.to_owned()));
+                }
+                "selectionOnly" => settings.selection_only = bool_from_json(key, value)?,
+                "backgrounds" => settings.backgrounds = bool_from_json(key, value)?,
+                _ => return Err(format!("unknown option `{}`", key)),
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Convert to CEF's settings, the strings in which must be cleared with
+    /// `clear_cef` afterwards
+    pub(crate) unsafe fn to_cef(&self) -> _cef_pdf_print_settings_t {
+        let (width, height) = self.paper_size.microns();
+        let (margin_type, top, right, bottom, left) = match self.margins {
+            Margins::Default => (
+                cef_pdf_print_margin_type_t_PDF_PRINT_MARGIN_DEFAULT,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+            ),
+            Margins::None => (
+                cef_pdf_print_margin_type_t_PDF_PRINT_MARGIN_NONE,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+            ),
+            Margins::Minimum => (
+                cef_pdf_print_margin_type_t_PDF_PRINT_MARGIN_MINIMUM,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+            ),
+            Margins::Custom {
+                top,
+                right,
+                bottom,
+                left,
+            } => (
+                cef_pdf_print_margin_type_t_PDF_PRINT_MARGIN_CUSTOM,
+                top,
+                right,
+                bottom,
+                left,
+            ),
+        };
+        let (title, url) = match &self.header_footer {
+            Some((title, url)) => (to_cef_string(title), to_cef_string(url)),
+            None => (cef_string_t::default(), cef_string_t::default()),
+        };
+
+        _cef_pdf_print_settings_t {
+            header_footer_title: title,
+            header_footer_url: url,
+            // CEF wants the page size in portrait, it rotates for landscape
+            page_width: width,
+            page_height: height,
+            scale_factor: (self.scale * 100.0).round() as i32,
+            margin_top: top,
+            margin_right: right,
+            margin_bottom: bottom,
+            margin_left: left,
+            margin_type,
+            header_footer_enabled: self.header_footer.is_some() as i32,
+            selection_only: self.selection_only as i32,
+            landscape: self.landscape as i32,
+            backgrounds_enabled: self.backgrounds as i32,
+        }
+    }
+
+    pub(crate) unsafe fn clear_cef(settings: &mut _cef_pdf_print_settings_t) {
+        cef_string_utf16_clear(&mut settings.header_footer_title);
+        cef_string_utf16_clear(&mut settings.header_footer_url);
+    }
+}
+
+fn paper_size_from_json(value: &Value) -> Result<PaperSize, String> {
+    match value {
+        Value::String(name) => match name.to_lowercase().as_str() {
+            "a3" => Ok(PaperSize::A3),
+            "a4" => Ok(PaperSize::A4),
+            "a5" => Ok(PaperSize::A5),
+            "letter" => Ok(PaperSize::Letter),
+            "legal" => Ok(PaperSize::Legal),
+            _ => Err(format!("unknown paper size `{}`", name)),
+        },
+        Value::Object(_) => {
+            let width = number_from_json("paperSize.width", &value["width"])?;
+            let height = number_from_json("paperSize.height", &value["height"])?;
+            match value["unit"].as_str().unwrap_or("mm") {
+                "mm" => Ok(PaperSize::millimeters(width, height)),
+                "in" => Ok(PaperSize::inches(width, height)),
+                unit => Err(format!("unknown paper size unit `{}`", unit)),
+            }
+        }
+        _ => Err("`paperSize` must be a name or an object".to_owned()),
+    }
+}
+
+fn margins_from_json(value: &Value) -> Result<Margins, String> {
+    match value {
+        Value::String(name) => match name.as_str() {
+            "default" => Ok(Margins::Default),
+            "none" => Ok(Margins::None),
+            "minimum" => Ok(Margins::Minimum),
+            _ => Err(format!("unknown margins `{}`", name)),
+        },
+        Value::Object(_) => Ok(Margins::Custom {
+            top: value["top"].as_f64().unwrap_or(0.0),
+            right: value["right"].as_f64().unwrap_or(0.0),
+            bottom: value["bottom"].as_f64().unwrap_or(0.0),
+            left: value["left"].as_f64().unwrap_or(0.0),
+        }),
+        _ => Err("`margins` must be a name or an object".to_owned()),
+    }
+}
+
+fn bool_from_json(key: &str, value: &Value) -> Result<bool, String> {
+    value
+        .as_bool()
+        .ok_or_else(|| format!("`{}` must be a boolean", key))
+}
+
+fn number_from_json(key: &str, value: &Value) -> Result<f64, String> {
+    value
+        .as_f64()
+        .ok_or_else(|| format!("`{}` must be a number", key))
+}
@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use super::request_context::RequestContext;
+
+/// How the browser connects through a proxy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxySettings {
+    /// Never use a proxy
+    Direct,
+    /// Auto-detect the proxy configuration (WPAD)
+    AutoDetect,
+    /// Use the operating system's proxy settings
+    System,
+    /// Use fixed proxy servers, i.e. `http=proxy:8080;https=proxy:8443`,
+    /// optionally bypassing them for a comma-separated list of hosts
+    FixedServers {
+        servers: String,
+        bypass_list: Option<String>,
+    },
+    /// Use a PAC script at the given URL
+    PacScript { url: String },
+}
+
+/// Which network interfaces WebRTC may use, mainly to avoid leaking local IP
+/// addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebRtcIpHandlingPolicy {
+    Default,
+    DefaultPublicAndPrivateInterfaces,
+    DefaultPublicInterfaceOnly,
+    DisableNonProxiedUdp,
+}
+
+impl WebRtcIpHandlingPolicy {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WebRtcIpHandlingPolicy::Default => "default",
+            WebRtcIpHandlingPolicy::DefaultPublicAndPrivateInterfaces => {
+                "default_public_and_private_interfaces"
+            }
+            WebRtcIpHandlingPolicy::DefaultPublicInterfaceOnly => "default_public_interface_only",
+            WebRtcIpHandlingPolicy::DisableNonProxiedUdp => "disable_non_proxied_udp",
+        }
+    }
+}
+
+impl RequestContext {
+    /// Where downloads get saved to by default
+    pub fn download_directory(&self) -> Option<PathBuf> {
+        self.get_preference("download.default_directory")
+            .and_then(|value| value.as_str().map(PathBuf::from))
+    }
+
+    pub fn set_download_directory<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.set_preference(
+            "download.default_directory",
+            json!(path.as_ref().display().to_string()),
+        )
+    }
+
+    /// Spellcheck with the given dictionaries, i.e. `["en-US", "fr"]`. An
+    /// empty list turns spellchecking off
+    pub fn set_spellcheck_dictionaries(
+        &self,
+        dictionaries: &[&str],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.set_preference("spellcheck.dictionaries", json!(dictionaries))?;
+        self.set_preference("browser.enable_spellchecking", json!(!dictionaries.is_empty()))
+    }
+
+    pub fn set_proxy(
+        &self,
+        proxy: &ProxySettings,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let value: Value = match proxy {
+            ProxySettings::Direct => json!({ "mode": "direct" }),
+            ProxySettings::AutoDetect => json!({ "mode": "auto_detect" }),
+            ProxySettings::System => json!({ "mode": "system" }),
+            ProxySettings::FixedServers {
+                servers,
+                bypass_list,
+            } => {
+                let mut value = json!({ "mode": "fixed_servers", "server": servers });
+                if let Some(bypass_list) = bypass_list {
+                    value["bypass_list"] = json!(bypass_list);
+                }
+                value
+            }
+            ProxySettings::PacScript { url } => json!({ "mode": "pac_script", "pac_url": url }),
+        };
+        self.set_preference("proxy", value)
+    }
+
+    pub fn set_webrtc_ip_handling_policy(
+        &self,
+        policy: WebRtcIpHandlingPolicy,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.set_preference("webrtc.ip_handling_policy", json!(policy.as_str()))
+    }
+}
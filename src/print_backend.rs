@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::pdf_print_settings::PaperSize;
+
+/// How to print on both sides of the paper
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplex {
+    /// Leave it up to the printer
+    Default,
+    /// One sided
+    Simplex,
+    /// Flip along the long edge, as in a book
+    LongEdge,
+    /// Flip along the short edge, as in a notepad
+    ShortEdge,
+}
+
+/// The settings of a print job
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintSettings {
+    /// The printer to print to, `None` for the default one
+    pub printer: Option<String>,
+    pub paper_size: PaperSize,
+    pub landscape: bool,
+    pub copies: i32,
+    pub collate: bool,
+    /// Colour rather than grayscale
+    pub color: bool,
+    pub duplex: Duplex,
+    /// Only print the current selection
+    pub selection_only: bool,
+    /// Inclusive ranges of pages to print, starting at 1; empty for all pages
+    pub page_ranges: Vec<(i32, i32)>,
+    /// Dots per inch, 0 for the printer's default
+    pub dpi: i32,
+}
+
+impl Default for PrintSettings {
+    fn default() -> PrintSettings {
+        PrintSettings {
+            printer: None,
+            paper_size: PaperSize::A4,
+            landscape: false,
+            copies: 1,
+            collate: true,
+            color: true,
+            duplex: Duplex::Default,
+            selection_only: false,
+            page_ranges: Vec::new(),
+            dpi: 0,
+        }
+    }
+}
+
+/// Does the actual printing for `window.print()` and `Browser::print`,
+/// standing in for a native print dialog and print system
+///
+/// Note that CEF only asks for this on Linux, where there's no native print
+/// dialog to fall back on; on Windows the operating system's own print
+/// dialog is used
+pub trait PrintBackend {
+    /// The settings to start from before any dialog is shown
+    fn default_settings(&mut self) -> PrintSettings {
+        PrintSettings::default()
+    }
+
+    /// Show a print dialog, returning the chosen settings or `None` to cancel
+    /// printing. By default, prints with `settings` without asking
+    fn print_dialog(
+        &mut self,
+        has_selection: bool,
+        settings: &PrintSettings,
+    ) -> Option<PrintSettings> {
+        let _ = has_selection;
+        Some(settings.clone())
+    }
+
+    /// Print `pdf`, which holds the rendered document and is removed again
+    /// once this returns
+    fn print_job(
+        &mut self,
+        document_name: &str,
+        pdf: &Path,
+        settings: &PrintSettings,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Prints by saving the document as a PDF file
+#[derive(Debug, Clone)]
+pub struct PdfFileBackend {
+    path: PathBuf,
+}
+
+impl PdfFileBackend {
+    /// Save to `path`, or to a file named after the document inside `path`
+    /// if it is a directory
+    pub fn new<P: Into<PathBuf>>(path: P) -> PdfFileBackend {
+        PdfFileBackend { path: path.into() }
+    }
+}
+
+impl PrintBackend for PdfFileBackend {
+    fn print_job(
+        &mut self,
+        document_name: &str,
+        pdf: &Path,
+        _settings: &PrintSettings,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = if self.path.is_dir() {
+            let name: String = document_name
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            let name = if name.trim().is_empty() {
+                "document"
+            } else {
+                name.trim()
+            };
+            self.path.join(format!("{}.pdf", name))
+        } else {
+            self.path.clone()
+        };
+        log::debug!(
+            "saving print job `{}` to `{}`",
+            document_name,
+            path.display()
+        );
+        std::fs::copy(pdf, &path)?;
+        Ok(())
+    }
+}
+
+/// Prints by handing the document to the CUPS `lp` command
+#[derive(Debug, Clone)]
+pub struct LpBackend {
+    command: PathBuf,
+}
+
+impl LpBackend {
+    pub fn new() -> LpBackend {
+        LpBackend {
+            command: PathBuf::from("lp"),
+        }
+    }
+
+    /// Run a different `lp` compatible command
+    pub fn command<P: Into<PathBuf>>(mut self, command: P) -> LpBackend {
+        self.command = command.into();
+        self
+    }
+}
+
+impl Default for LpBackend {
+    fn default() -> LpBackend {
+        LpBackend::new()
+    }
+}
+
+impl PrintBackend for LpBackend {
+    fn print_job(
+        &mut self,
+        document_name: &str,
+        pdf: &Path,
+        settings: &PrintSettings,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut command = Command::new(&self.command);
+        command.arg("-t").arg(document_name);
+        if let Some(printer) = &settings.printer {
+            command.arg("-d").arg(printer);
+        }
+        command.arg("-n").arg(settings.copies.max(1).to_string());
+        if settings.landscape {
+            command.arg("-o").arg("landscape");
+        }
+        if !settings.collate {
+            command.arg("-o").arg("collate=false");
+        }
+        match settings.duplex {
+            Duplex::Default => {}
+            Duplex::Simplex => {
+                command.arg("-o").arg("sides=one-sided");
+            }
+            Duplex::LongEdge => {
+                command.arg("-o").arg("sides=two-sided-long-edge");
+            }
+            Duplex::ShortEdge => {
+                command.arg("-o").arg("sides=two-sided-short-edge");
+            }
+        }
+        if !settings.color {
+            command.arg("-o").arg("print-color-mode=monochrome");
+        }
+        if !settings.page_ranges.is_empty() {
+            let ranges: Vec<String> = settings
+                .page_ranges
+                .iter()
+                .map(|&(from, to)| {
+                    if from == to {
+                        from.to_string()
+                    } else {
+                        format!("{}-{}", from, to)
+                    }
+                })
+                .collect();
+            command.arg("-P").arg(ranges.join(","));
+        }
+        command.arg("--").arg(pdf);
+
+        log::debug!("printing `{}` with {:?}", document_name, command);
+        let output = command.output()?;
+        if !output.status.success() {
+            return Err(format!(
+                "`{}` failed: {}",
+                self.command.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
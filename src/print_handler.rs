@@ -0,0 +1,332 @@
+use std::mem::size_of;
+use std::os::raw::c_int;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::bindings::{
+    cef_base_ref_counted_t, cef_browser_t, cef_color_model_t_COLOR_MODEL_COLOR,
+    cef_color_model_t_COLOR_MODEL_GRAY, cef_duplex_mode_t_DUPLEX_MODE_LONG_EDGE,
+    cef_duplex_mode_t_DUPLEX_MODE_SHORT_EDGE, cef_duplex_mode_t_DUPLEX_MODE_SIMPLEX,
+    cef_duplex_mode_t_DUPLEX_MODE_UNKNOWN, cef_print_dialog_callback_t, cef_print_handler_t,
+    cef_print_job_callback_t, cef_print_settings_create, cef_print_settings_t, cef_range_t,
+    cef_size_t, cef_string_t, cef_string_utf16_clear, size_t,
+};
+use super::print_backend::{Duplex, PrintBackend, PrintSettings};
+use super::value::{from_cef_string, from_cef_userfree, to_cef_string};
+
+const MICRONS_PER_INCH: i64 = 25400;
+
+#[repr(C)]
+pub struct PrintHandler {
+    handler: cef_print_handler_t,
+    ref_count: AtomicUsize,
+    backend: Option<Box<dyn PrintBackend + Send>>,
+    /// The settings of the print in progress
+    settings: PrintSettings,
+}
+
+unsafe extern "C" fn on_print_start(slf: *mut cef_print_handler_t, _browser: *mut cef_browser_t) {
+    let handler = slf as *mut PrintHandler;
+    (*handler).settings = match &mut (*handler).backend {
+        Some(backend) => backend.default_settings(),
+        None => PrintSettings::default(),
+    };
+}
+
+unsafe extern "C" fn on_print_settings(
+    slf: *mut cef_print_handler_t,
+    _browser: *mut cef_browser_t,
+    settings: *mut cef_print_settings_t,
+    get_defaults: c_int,
+) {
+    let handler = slf as *mut PrintHandler;
+    if get_defaults == 1 {
+        write_settings(settings, &(*handler).settings);
+    } else {
+        // the page changed the settings, i.e. through print preview
+        (*handler).settings = read_settings(settings, &(*handler).settings);
+    }
+}
+
+unsafe extern "C" fn on_print_dialog(
+    slf: *mut cef_print_handler_t,
+    _browser: *mut cef_browser_t,
+    has_selection: c_int,
+    callback: *mut cef_print_dialog_callback_t,
+) -> c_int {
+    let handler = slf as *mut PrintHandler;
+    let chosen = match &mut (*handler).backend {
+        Some(backend) => backend.print_dialog(has_selection == 1, &(*handler).settings),
+        None => return 0,
+    };
+
+    match chosen {
+        Some(chosen) => {
+            let settings = cef_print_settings_create();
+            write_settings(settings, &chosen);
+            (*handler).settings = chosen;
+            // passing the settings hands our reference over to CEF
+            (*callback).cont.expect("cont is a function")(callback, settings);
+        }
+        None => (*callback).cancel.expect("cancel is a function")(callback),
+    }
+    1
+}
+
+unsafe extern "C" fn on_print_job(
+    slf: *mut cef_print_handler_t,
+    _browser: *mut cef_browser_t,
+    document_name: *const cef_string_t,
+    pdf_file_path: *const cef_string_t,
+    callback: *mut cef_print_job_callback_t,
+) -> c_int {
+    let handler = slf as *mut PrintHandler;
+    let backend = match &mut (*handler).backend {
+        Some(backend) => backend,
+        None => return 0,
+    };
+
+    let document_name = from_cef_string(document_name);
+    let pdf_file_path = from_cef_string(pdf_file_path);
+    if let Err(e) = backend.print_job(
+        &document_name,
+        Path::new(&pdf_file_path),
+        &(*handler).settings,
+    ) {
+        log::error!("failed to print `{}`: {}", document_name, e);
+    }
+
+    // CEF only offers to continue, so that's all we can do even if it failed
+    (*callback).cont.expect("cont is a function")(callback);
+    1
+}
+
+unsafe extern "C" fn on_print_reset(slf: *mut cef_print_handler_t, _browser: *mut cef_browser_t) {
+    let handler = slf as *mut PrintHandler;
+    (*handler).settings = PrintSettings::default();
+}
+
+unsafe extern "C" fn get_pdf_paper_size(
+    slf: *mut cef_print_handler_t,
+    device_units_per_inch: c_int,
+) -> cef_size_t {
+    let handler = slf as *mut PrintHandler;
+    let (width, height) = (*handler).settings.paper_size.microns();
+    let to_device_units =
+        |microns: i32| (microns as i64 * device_units_per_inch as i64 / MICRONS_PER_INCH) as c_int;
+    cef_size_t {
+        width: to_device_units(width),
+        height: to_device_units(height),
+    }
+}
+
+/// Read CEF's print settings, keeping anything CEF doesn't know about from
+/// `current`
+unsafe fn read_settings(
+    settings: *mut cef_print_settings_t,
+    current: &PrintSettings,
+) -> PrintSettings {
+    let printer = from_cef_userfree((*settings)
+        .get_device_name
+        .expect("get_device_name is a function")(settings));
+
+    let count = (*settings)
+        .get_page_ranges_count
+        .expect("get_page_ranges_count is a function")(settings);
+    let mut ranges = vec![cef_range_t::default(); count as usize];
+    let mut count: size_t = count;
+    if !ranges.is_empty() {
+        (*settings)
+            .get_page_ranges
+            .expect("get_page_ranges is a function")(
+            settings, &mut count, ranges.as_mut_ptr()
+        );
+    }
+    ranges.truncate(count as usize);
+
+    let color_model = (*settings)
+        .get_color_model
+        .expect("get_color_model is a function")(settings);
+    let duplex = (*settings)
+        .get_duplex_mode
+        .expect("get_duplex_mode is a function")(settings);
+
+    PrintSettings {
+        printer: if printer.is_empty() {
+            None
+        } else {
+            Some(printer)
+        },
+        paper_size: current.paper_size,
+        landscape: (*settings)
+            .is_landscape
+            .expect("is_landscape is a function")(settings)
+            == 1,
+        copies: (*settings).get_copies.expect("get_copies is a function")(settings),
+        collate: (*settings)
+            .will_collate
+            .expect("will_collate is a function")(settings)
+            == 1,
+        color: color_model != cef_color_model_t_COLOR_MODEL_GRAY,
+        duplex: if duplex == cef_duplex_mode_t_DUPLEX_MODE_SIMPLEX {
+            Duplex::Simplex
+        } else if duplex == cef_duplex_mode_t_DUPLEX_MODE_LONG_EDGE {
+            Duplex::LongEdge
+        } else if duplex == cef_duplex_mode_t_DUPLEX_MODE_SHORT_EDGE {
+            Duplex::ShortEdge
+        } else {
+            Duplex::Default
+        },
+        selection_only: (*settings)
+            .is_selection_only
+            .expect("is_selection_only is a function")(settings)
+            == 1,
+        // CEF's page ranges start at 0
+        page_ranges: ranges
+            .iter()
+            .map(|range| (range.from + 1, range.to + 1))
+            .collect(),
+        dpi: (*settings).get_dpi.expect("get_dpi is a function")(settings),
+    }
+}
+
+/// Write our print settings into CEF's, if they can be changed
+unsafe fn write_settings(settings: *mut cef_print_settings_t, from: &PrintSettings) {
+    if (*settings)
+        .is_read_only
+        .expect("is_read_only is a function")(settings)
+        == 1
+    {
+        log::warn!("print settings are read only, ignoring ours");
+        return;
+    }
+
+    let mut printer = to_cef_string(from.printer.as_deref().unwrap_or(""));
+    (*settings)
+        .set_device_name
+        .expect("set_device_name is a function")(settings, &printer);
+    cef_string_utf16_clear(&mut printer);
+
+    let ranges: Vec<cef_range_t> = from
+        .page_ranges
+        .iter()
+        .map(|&(from, to)| cef_range_t {
+            from: from - 1,
+            to: to - 1,
+        })
+        .collect();
+    (*settings)
+        .set_page_ranges
+        .expect("set_page_ranges is a function")(
+        settings, ranges.len() as size_t, ranges.as_ptr()
+    );
+
+    (*settings)
+        .set_orientation
+        .expect("set_orientation is a function")(settings, from.landscape as c_int);
+    (*settings).set_copies.expect("set_copies is a function")(settings, from.copies);
+    (*settings).set_collate.expect("set_collate is a function")(settings, from.collate as c_int);
+    (*settings)
+        .set_color_model
+        .expect("set_color_model is a function")(
+        settings,
+        if from.color {
+            cef_color_model_t_COLOR_MODEL_COLOR
+        } else {
+            cef_color_model_t_COLOR_MODEL_GRAY
+        },
+    );
+    (*settings)
+        .set_duplex_mode
+        .expect("set_duplex_mode is a function")(
+        settings,
+        match from.duplex {
+            Duplex::Default => cef_duplex_mode_t_DUPLEX_MODE_UNKNOWN,
+            Duplex::Simplex => cef_duplex_mode_t_DUPLEX_MODE_SIMPLEX,
+            Duplex::LongEdge => cef_duplex_mode_t_DUPLEX_MODE_LONG_EDGE,
+            Duplex::ShortEdge => cef_duplex_mode_t_DUPLEX_MODE_SHORT_EDGE,
+        },
+    );
+    (*settings)
+        .set_selection_only
+        .expect("set_selection_only is a function")(settings, from.selection_only as c_int);
+    if from.dpi > 0 {
+        (*settings).set_dpi.expect("set_dpi is a function")(settings, from.dpi);
+    }
+}
+
+pub fn allocate() -> *mut PrintHandler {
+    let handler = PrintHandler {
+        handler: cef_print_handler_t {
+            base: cef_base_ref_counted_t {
+                size: size_of::<PrintHandler>() as u64,
+                add_ref: Some(add_ref),
+                release: Some(release),
+                has_one_ref: Some(has_one_ref),
+                has_at_least_one_ref: Some(has_at_least_one_ref),
+            },
+            on_print_start: Some(on_print_start),
+            on_print_settings: Some(on_print_settings),
+            on_print_dialog: Some(on_print_dialog),
+            on_print_job: Some(on_print_job),
+            on_print_reset: Some(on_print_reset),
+            get_pdf_paper_size: Some(get_pdf_paper_size),
+        },
+        ref_count: AtomicUsize::new(1),
+        backend: None,
+        settings: PrintSettings::default(),
+    };
+
+    super::ref_counting::allocated("PrintHandler");
+    Box::into_raw(Box::from(handler))
+}
+
+pub unsafe fn set_backend(slf: *mut PrintHandler, backend: Box<dyn PrintBackend + Send>) {
+    (*slf).backend = Some(backend);
+}
+
+pub unsafe fn has_backend(slf: *mut PrintHandler) -> bool {
+    (*slf).backend.is_some()
+}
+
+extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
+    let print_handler = base as *mut PrintHandler;
+    unsafe {
+        (*print_handler).ref_count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
+    let print_handler = base as *mut PrintHandler;
+    let count = unsafe { (*print_handler).ref_count.fetch_sub(1, Ordering::SeqCst) - 1 };
+
+    if count == 0 {
+        unsafe {
+            Box::from_raw(print_handler);
+            super::ref_counting::freed("PrintHandler");
+        }
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let print_handler = base as *mut PrintHandler;
+    let count = unsafe { (*print_handler).ref_count.load(Ordering::SeqCst) };
+    if count == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_at_least_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let print_handler = base as *mut PrintHandler;
+    let count = unsafe { (*print_handler).ref_count.load(Ordering::SeqCst) };
+    if count >= 1 {
+        1
+    } else {
+        0
+    }
+}
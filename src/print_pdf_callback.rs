@@ -35,6 +35,7 @@ pub fn allocate(on_done: Option<Box<dyn FnMut(bool)>>) -> *mut PDFPrintCallback
         on_done,
     };
 
+    super::ref_counting::allocated("PDFPrintCallback");
     Box::into_raw(Box::from(handler))
 }
 
@@ -59,6 +60,7 @@ extern "C" fn release_pdf_print_callback(base: *mut cef_base_ref_counted_t) -> c
     if count == 0 {
         unsafe {
             Box::from_raw(life_span_handler);
+            super::ref_counting::freed("PDFPrintCallback");
         }
         1
     } else {
@@ -0,0 +1,249 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::task::{waker, ArcWake};
+
+use super::schedule::Schedule;
+use super::{Cef, MessageLoopMode};
+
+/// How often the tokio pump checks for window messages. A thread's message
+/// queue can't be awaited, so this is the one thing it has to poll for. CEF's
+/// work has to be polled too with `MessageLoopMode::RunMessageLoop`, which
+/// doesn't schedule it
+#[cfg(feature = "tokio")]
+const MESSAGE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Wakes the thread running `Cef::run_until`
+struct FutureWaker {
+    schedule: Arc<Schedule>,
+    woken: AtomicBool,
+    /// Whether `run_until` is blocked in `cef_run_message_loop`
+    in_cef_loop: AtomicBool,
+    /// Whether `run_until` has returned, so late wake-ups leave CEF's loop be
+    done: AtomicBool,
+}
+
+impl ArcWake for FutureWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.woken.store(true, Ordering::SeqCst);
+        arc_self.schedule.wake();
+        if arc_self.in_cef_loop.load(Ordering::SeqCst) {
+            // CEF's own loop can only be quit from the UI thread. Check again
+            // once there, the future may have been polled in the meantime
+            let future_waker = arc_self.clone();
+            super::task::post(
+                super::task::Thread::UI,
+                Box::from(move || {
+                    if !future_waker.done.load(Ordering::SeqCst) && future_waker.woken.load(Ordering::SeqCst) {
+                        unsafe { super::bindings::cef_quit_message_loop() };
+                    }
+                }),
+            );
+        }
+    }
+}
+
+impl Cef {
+    /// Process pending window messages and whatever work CEF has scheduled.
+    /// Returns the exit code if a `WM_QUIT` message came through
+    pub fn pump(&self) -> Option<i32> {
+        let mut quit = None;
+
+        #[cfg(windows)]
+        unsafe {
+            use winapi::um::winuser::{DispatchMessageW, PeekMessageW, TranslateMessage, PM_REMOVE, WM_QUIT};
+            let mut msg = std::mem::MaybeUninit::uninit();
+            while PeekMessageW(msg.as_mut_ptr(), std::ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+                let msg = msg.assume_init();
+                if msg.message == WM_QUIT {
+                    quit = Some(msg.wParam as i32);
+                    break;
+                }
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+
+        match self.mode {
+            MessageLoopMode::ExternalPump => {
+                if self.should_do_work() {
+                    self.do_message_loop_work();
+                }
+            }
+            // not driven by the scheduler; `run` and `run_until` block in
+            // CEF's own loop instead, so this is a single manual tick
+            MessageLoopMode::RunMessageLoop => self.do_message_loop_work(),
+            // CEF pumps itself
+            MessageLoopMode::MultiThreaded => {}
+        }
+
+        quit
+    }
+
+    /// Drive CEF and the window messages of the current thread until `future`
+    /// completes, then return its output. Browser futures such as
+    /// `Browser::eval` resolve while this runs. A `WM_QUIT` received in the
+    /// meantime is posted again before returning. With
+    /// `MessageLoopMode::RunMessageLoop`, this blocks in CEF's own loop, which
+    /// is quit whenever the future is woken
+    pub fn run_until<F: Future>(&self, future: F) -> F::Output {
+        futures::pin_mut!(future);
+
+        let future_waker = Arc::new(FutureWaker {
+            schedule: self.schedule.clone(),
+            woken: AtomicBool::new(true),
+            in_cef_loop: AtomicBool::new(false),
+            done: AtomicBool::new(false),
+        });
+        let waker = waker(future_waker.clone());
+        let mut context = Context::from_waker(&waker);
+        let mut quit = None;
+
+        let output = loop {
+            // anything waking us from here on interrupts the wait below
+            self.schedule.clear_wake();
+            if future_waker.woken.swap(false, Ordering::SeqCst) {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                    break output;
+                }
+            }
+
+            if self.mode == MessageLoopMode::RunMessageLoop {
+                // let CEF block in its own loop until the future is woken.
+                // Flag that first, so a wake-up from here on quits the loop
+                future_waker.in_cef_loop.store(true, Ordering::SeqCst);
+                if !future_waker.woken.load(Ordering::SeqCst) {
+                    unsafe { super::bindings::cef_run_message_loop() };
+                }
+                future_waker.in_cef_loop.store(false, Ordering::SeqCst);
+                continue;
+            }
+
+            if let Some(code) = self.pump() {
+                quit = Some(code);
+            }
+
+            if !future_waker.woken.load(Ordering::SeqCst) {
+                self.wait_for_events();
+            }
+        };
+        future_waker.done.store(true, Ordering::SeqCst);
+
+        #[cfg(windows)]
+        {
+            if let Some(code) = quit {
+                unsafe { winapi::um::winuser::PostQuitMessage(code) };
+            }
+        }
+        #[cfg(not(windows))]
+        let _ = quit;
+
+        output
+    }
+
+    /// Run the message loop until `quit` is called, a `WM_QUIT` message comes
+    /// through, or, if asked to, the last browser closes. Uses CEF's own
+    /// loop with `MessageLoopMode::RunMessageLoop`, and pumps CEF and the
+    /// current thread's window messages otherwise
+    pub fn run(&self) {
+        if self.mode == MessageLoopMode::RunMessageLoop {
+            // flag that first, so a quit from here on ends the loop
+            self.schedule.set_in_cef_loop(true);
+            if !self.schedule.is_quitting() {
+                unsafe { super::bindings::cef_run_message_loop() };
+            }
+            self.schedule.set_in_cef_loop(false);
+            self.schedule.take_quit();
+            return;
+        }
+
+        loop {
+            self.schedule.clear_wake();
+            if self.schedule.take_quit() {
+                break;
+            }
+            if self.pump().is_some() {
+                break;
+            }
+            self.wait_for_events();
+        }
+    }
+
+    /// Make `run` return. Use `PumpWaker::quit` to quit from other threads
+    pub fn quit(&self) {
+        self.schedule.quit();
+    }
+
+    /// Have `run` return once the last browser has closed
+    pub fn set_quit_on_last_browser_closed(&self, quit: bool) {
+        self.registry
+            .set_quit_when_empty(if quit { Some(self.waker()) } else { None });
+    }
+
+    /// Sleep until there's CEF work due, a window message, or a wake-up
+    fn wait_for_events(&self) {
+        let timeout = match self.mode {
+            MessageLoopMode::ExternalPump => self.time_until_work(),
+            // `run` and `run_until` block in CEF's own loop instead
+            MessageLoopMode::RunMessageLoop => None,
+            MessageLoopMode::MultiThreaded => None,
+        };
+
+        #[cfg(windows)]
+        unsafe {
+            use winapi::um::winbase::INFINITE;
+            use winapi::um::winuser::{MsgWaitForMultipleObjects, QS_ALLINPUT};
+            use std::os::windows::io::AsRawHandle;
+
+            let handle = self.wake_handle().as_raw_handle() as winapi::um::winnt::HANDLE;
+            let timeout = timeout.map_or(INFINITE, |t| t.as_millis().min(INFINITE as u128 - 1) as u32);
+            MsgWaitForMultipleObjects(1, &handle, 0, timeout, QS_ALLINPUT);
+        }
+        #[cfg(not(windows))]
+        {
+            self.wait_for_work(timeout);
+        }
+    }
+
+    /// Pump CEF forever from within a tokio `LocalSet`, i.e. alongside the
+    /// rest of an async application:
+    ///
+    /// ```ignore
+    /// let local = tokio::task::LocalSet::new();
+    /// local.run_until(async {
+    ///     tokio::select! {
+    ///         _ = cef.run_pump() => {},
+    ///         _ = app() => {},
+    ///     }
+    /// }).await;
+    /// ```
+    ///
+    /// The pump finishes when a `WM_QUIT` message comes through
+    #[cfg(feature = "tokio")]
+    pub async fn run_pump(&self) -> i32 {
+        use futures::future::{self, Either};
+
+        loop {
+            if let Some(code) = self.pump() {
+                return code;
+            }
+
+            // sleep until CEF's work is due or the schedule is woken, i.e.
+            // because CEF rescheduled its work from another thread
+            let mut deadline = self.schedule.deadline();
+            if cfg!(windows) || self.mode == MessageLoopMode::RunMessageLoop {
+                let next_poll = std::time::Instant::now() + MESSAGE_POLL_INTERVAL;
+                deadline = Some(deadline.map_or(next_poll, |deadline| deadline.min(next_poll)));
+            }
+            let woken = self.schedule.woken();
+            let due = match deadline {
+                Some(deadline) => Either::Left(tokio::time::delay_until(tokio::time::Instant::from_std(deadline))),
+                None => Either::Right(future::pending::<()>()),
+            };
+            futures::pin_mut!(woken, due);
+            future::select(woken, due).await;
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::ffi::c_void;
+
+#[cfg(windows)]
+use raw_window_handle::windows::WindowsHandle;
+#[cfg(target_os = "linux")]
+use raw_window_handle::unix::{XcbHandle, XlibHandle};
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+
+/// Wraps a native window handle that doesn't come from a windowing library,
+/// i.e. one created directly with the Win32 API or Xlib, so that it can be
+/// used as the parent of a browser
+#[derive(Debug, Clone, Copy)]
+pub struct RawWindow {
+    handle: RawWindowHandle,
+}
+
+impl RawWindow {
+    /// Wrap a Win32 `HWND`
+    #[cfg(windows)]
+    pub fn from_hwnd(hwnd: *mut c_void) -> RawWindow {
+        RawWindow {
+            handle: RawWindowHandle::Windows(WindowsHandle {
+                hwnd,
+                ..WindowsHandle::empty()
+            }),
+        }
+    }
+
+    /// Wrap an Xlib `Window` on the given `Display`
+    #[cfg(target_os = "linux")]
+    pub fn from_xlib_window(window: std::os::raw::c_ulong, display: *mut c_void) -> RawWindow {
+        RawWindow {
+            handle: RawWindowHandle::Xlib(XlibHandle {
+                window,
+                display,
+                ..XlibHandle::empty()
+            }),
+        }
+    }
+
+    /// Wrap an Xcb window on the given connection
+    #[cfg(target_os = "linux")]
+    pub fn from_xcb_window(window: u32, connection: *mut c_void) -> RawWindow {
+        RawWindow {
+            handle: RawWindowHandle::Xcb(XcbHandle {
+                window,
+                connection,
+                ..XcbHandle::empty()
+            }),
+        }
+    }
+
+    pub fn from_raw_window_handle(handle: RawWindowHandle) -> RawWindow {
+        RawWindow { handle }
+    }
+}
+
+unsafe impl HasRawWindowHandle for RawWindow {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        self.handle
+    }
+}
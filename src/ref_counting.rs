@@ -0,0 +1,80 @@
+//! Helpers for the reference counted objects we share with CEF. With the
+//! `leak-tracking` feature, every object we allocate for CEF is counted by
+//! type so that anything still alive at shutdown can be reported
+
+use super::bindings::cef_base_ref_counted_t;
+
+/// Release a reference to any CEF object, ours or CEF's. Every CEF struct
+/// starts with its `cef_base_ref_counted_t`
+pub unsafe fn release<T>(object: *mut T) {
+    if !object.is_null() {
+        let base = object as *mut cef_base_ref_counted_t;
+        (*base).release.expect("release is a function")(base);
+    }
+}
+
+/// Take a new reference to any CEF object
+pub unsafe fn add_ref<T>(object: *mut T) {
+    if !object.is_null() {
+        let base = object as *mut cef_base_ref_counted_t;
+        (*base).add_ref.expect("add_ref is a function")(base);
+    }
+}
+
+/// The number of live objects of each type
+#[cfg(feature = "leak-tracking")]
+static COUNTS: std::sync::Mutex<std::collections::BTreeMap<&'static str, usize>> =
+    std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+/// Note that an object of the given type was allocated
+#[inline]
+pub fn allocated(_name: &'static str) {
+    #[cfg(feature = "leak-tracking")]
+    {
+        let mut counts = COUNTS.lock().expect("live object counts aren't poisoned");
+        *counts.entry(_name).or_insert(0) += 1;
+    }
+}
+
+/// Note that an object of the given type was freed
+#[inline]
+pub fn freed(_name: &'static str) {
+    #[cfg(feature = "leak-tracking")]
+    {
+        let mut counts = COUNTS.lock().expect("live object counts aren't poisoned");
+        if let Some(count) = counts.get_mut(_name) {
+            *count = count.saturating_sub(1);
+        }
+    }
+}
+
+/// The number of objects of each type that are still alive, leaving out
+/// types with nothing alive. Always empty without the `leak-tracking` feature
+pub fn live_objects() -> Vec<(&'static str, usize)> {
+    #[cfg(feature = "leak-tracking")]
+    {
+        let counts = COUNTS.lock().expect("live object counts aren't poisoned");
+        counts
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(name, count)| (*name, *count))
+            .collect()
+    }
+    #[cfg(not(feature = "leak-tracking"))]
+    {
+        Vec::new()
+    }
+}
+
+/// Log everything that is still alive
+pub fn report() {
+    let live = live_objects();
+    if live.is_empty() {
+        #[cfg(feature = "leak-tracking")]
+        log::debug!("no leaked objects");
+        return;
+    }
+    for (name, count) in live {
+        log::error!("leaked {} `{}` object(s)", count, name);
+    }
+}
@@ -9,6 +9,7 @@ use super::bindings::{
 };
 use super::v8_pdf_print_handler::{self, V8PDFPrintHandler};
 use super::v8_file_dialog_handler::{self, V8FileDialogHandler};
+use super::v8_drag_drop_handler::{self, V8DragDropHandler};
 
 #[repr(C)]
 pub struct RenderProcessHandler {
@@ -16,6 +17,7 @@ pub struct RenderProcessHandler {
     ref_count: AtomicUsize,
     pdf_print_extension: *mut V8PDFPrintHandler,
     file_dialog_extension: *mut V8FileDialogHandler,
+    drag_drop_extension: *mut V8DragDropHandler,
 }
 
 impl RenderProcessHandler {
@@ -28,12 +30,21 @@ unsafe extern "C" fn on_web_kit_initialized(slf: *mut cef_render_process_handler
     let _self = slf as *mut RenderProcessHandler;
     super::v8_pdf_print_handler::register_extension((*_self).pdf_print_extension);
     super::v8_file_dialog_handler::register_extension((*_self).file_dialog_extension);
+    super::v8_drag_drop_handler::register_extension((*_self).drag_drop_extension);
 }
 
 unsafe extern "C" fn on_context_created(slf: *mut cef_render_process_handler_t, _browser: *mut cef_browser_t, frame: *mut cef_frame_t, _context: *mut cef_v8context_t) {
     let _self = slf as *mut RenderProcessHandler;
-    (*(*_self).pdf_print_extension).frame = Some(frame);
-    (*(*_self).file_dialog_extension).frame = Some(frame);
+    replace_frame(&mut (*(*_self).pdf_print_extension).frame, frame);
+    replace_frame(&mut (*(*_self).file_dialog_extension).frame, frame);
+}
+
+/// Hold on to a new frame, releasing the one we held before
+unsafe fn replace_frame(slot: &mut Option<*mut cef_frame_t>, frame: *mut cef_frame_t) {
+    super::ref_counting::add_ref(frame);
+    if let Some(old) = slot.replace(frame) {
+        super::ref_counting::release(old);
+    }
 }
 
 unsafe extern "C" fn on_process_message_received(
@@ -59,6 +70,9 @@ unsafe extern "C" fn on_process_message_received(
     if super::v8_file_dialog_handler::process_message((*_self).file_dialog_extension, &message_name, message) {
         return 1;
     }
+    if super::v8_drag_drop_handler::process_message((*_self).drag_drop_extension, &message_name, message) {
+        return 1;
+    }
     log::warn!("unhandled process message in renderer: `{}`", message_name);
     0
 }
@@ -87,8 +101,10 @@ pub fn allocate() -> *mut RenderProcessHandler {
         ref_count: AtomicUsize::new(1),
         pdf_print_extension: v8_pdf_print_handler::allocate(),
         file_dialog_extension: v8_file_dialog_handler::allocate(),
+        drag_drop_extension: v8_drag_drop_handler::allocate(),
     };
 
+    super::ref_counting::allocated("RenderProcessHandler");
     Box::into_raw(Box::from(handler))
 }
 
@@ -103,7 +119,11 @@ extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
 
     if count == 0 {
         unsafe {
-            Box::from_raw(render_process_handler);
+            let handler: Box<RenderProcessHandler> = Box::from_raw(render_process_handler);
+            super::ref_counting::freed("RenderProcessHandler");
+            super::ref_counting::release(handler.pdf_print_extension);
+            super::ref_counting::release(handler.file_dialog_extension);
+            super::ref_counting::release(handler.drag_drop_extension);
         }
         1
     } else {
@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+use std::ptr::null_mut;
+
+use serde_json::Value;
+
+use super::bindings::{
+    cef_request_context_create_context, cef_request_context_get_global_context,
+    cef_request_context_settings_t, cef_request_context_t, cef_string_t, cef_string_utf16_clear,
+};
+use super::cookie_manager::CookieManager;
+use super::value::{cef_dictionary_to_json, cef_value_to_json, from_cef_string, from_cef_userfree, json_to_cef_value, to_cef_string};
+
+/// A request context holds its own cookies, cache and storage. Browsers that
+/// share a request context share all of those; give browsers separate
+/// contexts to isolate them from each other
+pub struct RequestContext {
+    context: *mut cef_request_context_t,
+}
+
+impl RequestContext {
+    /// Take ownership of a request context reference handed to us by CEF
+    pub(crate) unsafe fn from_raw(context: *mut cef_request_context_t) -> Option<RequestContext> {
+        if context.is_null() {
+            None
+        } else {
+            Some(RequestContext { context })
+        }
+    }
+
+    /// Get a new reference to the underlying context, to hand over to CEF
+    pub(crate) fn to_raw(&self) -> *mut cef_request_context_t {
+        unsafe {
+            (*self.context).base.add_ref.expect("add_ref is a function")(&mut (*self.context).base);
+        }
+        self.context
+    }
+
+    /// The context that browsers use unless told otherwise
+    pub fn global() -> RequestContext {
+        unsafe {
+            RequestContext::from_raw(cef_request_context_get_global_context())
+                .expect("the global request context exists")
+        }
+    }
+
+    pub fn is_global(&self) -> bool {
+        unsafe { (*self.context).is_global.expect("is_global is a function")(self.context) == 1 }
+    }
+
+    /// Whether both contexts share the same storage
+    pub fn is_sharing_with(&self, other: &RequestContext) -> bool {
+        unsafe {
+            (*self.context)
+                .is_sharing_with
+                .expect("is_sharing_with is a function")(self.context, other.context)
+                == 1
+        }
+    }
+
+    /// Where this context stores its data, `None` if it's kept in memory
+    pub fn cache_path(&self) -> Option<PathBuf> {
+        let path = unsafe {
+            from_cef_userfree((*self.context).get_cache_path.expect("get_cache_path is a function")(
+                self.context,
+            ))
+        };
+        if path.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(path))
+        }
+    }
+
+    /// The cookie manager of this context
+    pub fn cookie_manager(&self) -> Option<CookieManager> {
+        unsafe {
+            CookieManager::from_raw((*self.context)
+                .get_cookie_manager
+                .expect("get_cookie_manager is a function")(
+                self.context, null_mut()
+            ))
+        }
+    }
+
+    // note: preferences can only be accessed from the UI thread
+
+    /// Whether a preference with the given name exists
+    pub fn has_preference(&self, name: &str) -> bool {
+        unsafe {
+            let mut cef_name = to_cef_string(name);
+            let has = (*self.context).has_preference.expect("has_preference is a function")(
+                self.context,
+                &cef_name,
+            );
+            cef_string_utf16_clear(&mut cef_name);
+            has == 1
+        }
+    }
+
+    /// Get the current value of a preference, i.e. `download.default_directory`
+    pub fn get_preference(&self, name: &str) -> Option<Value> {
+        unsafe {
+            let mut cef_name = to_cef_string(name);
+            let cef_value = (*self.context).get_preference.expect("get_preference is a function")(
+                self.context,
+                &cef_name,
+            );
+            cef_string_utf16_clear(&mut cef_name);
+            if cef_value.is_null() {
+                return None;
+            }
+            let value = cef_value_to_json(cef_value);
+            (*cef_value).base.release.expect("release is a function")(&mut (*cef_value).base);
+            Some(value)
+        }
+    }
+
+    /// Get every preference as a JSON object keyed by preference name
+    pub fn all_preferences(&self, include_defaults: bool) -> Value {
+        unsafe {
+            let dictionary = (*self.context)
+                .get_all_preferences
+                .expect("get_all_preferences is a function")(
+                self.context,
+                include_defaults as i32,
+            );
+            if dictionary.is_null() {
+                return Value::Null;
+            }
+            let value = cef_dictionary_to_json(dictionary);
+            (*dictionary).base.release.expect("release is a function")(&mut (*dictionary).base);
+            value
+        }
+    }
+
+    /// Whether a preference exists and isn't managed by a command line switch
+    /// or policy
+    pub fn can_set_preference(&self, name: &str) -> bool {
+        unsafe {
+            let mut cef_name = to_cef_string(name);
+            let can = (*self.context)
+                .can_set_preference
+                .expect("can_set_preference is a function")(self.context, &cef_name);
+            cef_string_utf16_clear(&mut cef_name);
+            can == 1
+        }
+    }
+
+    /// Set a preference, setting it to `null` restores the default value
+    pub fn set_preference(
+        &self,
+        name: &str,
+        value: Value,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        unsafe {
+            let mut cef_name = to_cef_string(name);
+            let cef_value = match value {
+                Value::Null => null_mut(),
+                value => json_to_cef_value(&value),
+            };
+            let mut cef_error = cef_string_t::default();
+            let ok = (*self.context).set_preference.expect("set_preference is a function")(
+                self.context,
+                &cef_name,
+                cef_value,
+                &mut cef_error,
+            );
+            cef_string_utf16_clear(&mut cef_name);
+            let error = from_cef_string(&cef_error);
+            cef_string_utf16_clear(&mut cef_error);
+
+            if ok == 1 {
+                Ok(())
+            } else {
+                Err(Box::from(format!("failed to set preference `{}`: {}", name, error)))
+            }
+        }
+    }
+}
+
+impl Clone for RequestContext {
+    fn clone(&self) -> RequestContext {
+        RequestContext {
+            context: self.to_raw(),
+        }
+    }
+}
+
+impl Drop for RequestContext {
+    fn drop(&mut self) {
+        unsafe {
+            (*self.context).base.release.expect("release is a function")(&mut (*self.context).base);
+        }
+    }
+}
+
+/// Builds an isolated `RequestContext`
+#[derive(Debug, Clone, Default)]
+pub struct RequestContextBuilder {
+    cache_path: Option<PathBuf>,
+    accept_language_list: Option<String>,
+    persist_session_cookies: bool,
+    persist_user_preferences: bool,
+}
+
+impl RequestContextBuilder {
+    /// A builder for an in-memory ("incognito") context
+    pub fn new() -> RequestContextBuilder {
+        RequestContextBuilder::default()
+    }
+
+    /// Store the context's data on disk at `path`. CEF requires this to be a
+    /// child of the root cache path and falls back to keeping the data in
+    /// memory otherwise
+    pub fn cache_path<P: AsRef<Path>>(mut self, path: P) -> RequestContextBuilder {
+        self.cache_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// A comma-separated list of languages for the `Accept-Language` header,
+    /// i.e. `en-CA,en,fr`
+    pub fn accept_language_list(mut self, languages: &str) -> RequestContextBuilder {
+        self.accept_language_list = Some(languages.to_owned());
+        self
+    }
+
+    /// Keep session cookies (those without an expiry) across restarts. Only
+    /// has an effect when there is a cache path
+    pub fn persist_session_cookies(mut self, persist: bool) -> RequestContextBuilder {
+        self.persist_session_cookies = persist;
+        self
+    }
+
+    /// Keep user preferences across restarts. Only has an effect when there
+    /// is a cache path
+    pub fn persist_user_preferences(mut self, persist: bool) -> RequestContextBuilder {
+        self.persist_user_preferences = persist;
+        self
+    }
+
+    /// Create the context, CEF must already be initialized
+    pub fn build(self) -> Result<RequestContext, Box<dyn std::error::Error>> {
+        let mut settings = cef_request_context_settings_t::default();
+        settings.size = std::mem::size_of::<cef_request_context_settings_t>() as u64;
+        settings.persist_session_cookies = self.persist_session_cookies as i32;
+        settings.persist_user_preferences = self.persist_user_preferences as i32;
+        unsafe {
+            if let Some(cache_path) = &self.cache_path {
+                settings.cache_path = to_cef_string(&cache_path.display().to_string());
+            }
+            if let Some(languages) = &self.accept_language_list {
+                settings.accept_language_list = to_cef_string(languages);
+            }
+
+            let context = cef_request_context_create_context(&settings, null_mut());
+            cef_string_utf16_clear(&mut settings.cache_path);
+            cef_string_utf16_clear(&mut settings.accept_language_list);
+
+            RequestContext::from_raw(context).ok_or_else(|| Box::from("failed to create request context"))
+        }
+    }
+}
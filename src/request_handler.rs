@@ -3,14 +3,22 @@ use std::os::raw::{c_int};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use super::bindings::{
-    cef_base_ref_counted_t, cef_request_handler_t,
+    cef_auth_callback_t, cef_base_ref_counted_t, cef_browser_t, cef_errorcode_t, cef_request_callback_t,
+    cef_request_handler_t, cef_sslinfo_t, cef_string_t, cef_termination_status_t,
 };
+use super::certificate_policy::{self, CertificatePolicy};
+use super::crash_recovery::{self, CrashRecovery, RenderProcessTerminated};
+use super::credential_provider::{self, AuthRequest, CredentialProvider};
+use super::value::from_cef_string;
 
-#[derive(Debug)]
 #[repr(C)]
 pub struct RequestHandler {
     request_handler: cef_request_handler_t,
     ref_count: AtomicUsize,
+    certificate_policy: Option<CertificatePolicy>,
+    credential_provider: Option<Box<dyn CredentialProvider>>,
+    render_process_terminated: Option<Box<dyn FnMut(&RenderProcessTerminated)>>,
+    crash_recovery: Option<CrashRecovery>,
 }
 
 impl RequestHandler {
@@ -19,6 +27,75 @@ impl RequestHandler {
     }
 }
 
+unsafe extern "C" fn on_certificate_error(
+    slf: *mut cef_request_handler_t,
+    _browser: *mut cef_browser_t,
+    cert_error: cef_errorcode_t,
+    request_url: *const cef_string_t,
+    ssl_info: *mut cef_sslinfo_t,
+    callback: *mut cef_request_callback_t,
+) -> c_int {
+    let handler = slf as *mut RequestHandler;
+    if let Some(policy) = &mut (*handler).certificate_policy {
+        let url = from_cef_string(request_url);
+        certificate_policy::handle_certificate_error(policy, cert_error as i32, url, ssl_info, callback);
+        1
+    } else {
+        // no policy, cancel the request
+        0
+    }
+}
+
+unsafe extern "C" fn get_auth_credentials(
+    slf: *mut cef_request_handler_t,
+    _browser: *mut cef_browser_t,
+    origin_url: *const cef_string_t,
+    is_proxy: c_int,
+    host: *const cef_string_t,
+    port: c_int,
+    realm: *const cef_string_t,
+    scheme: *const cef_string_t,
+    callback: *mut cef_auth_callback_t,
+) -> c_int {
+    let handler = slf as *mut RequestHandler;
+    if let Some(provider) = &mut (*handler).credential_provider {
+        let request = AuthRequest {
+            origin_url: from_cef_string(origin_url),
+            is_proxy: is_proxy == 1,
+            host: from_cef_string(host),
+            port: port as u16,
+            realm: from_cef_string(realm),
+            scheme: from_cef_string(scheme),
+        };
+        credential_provider::provide_credentials(provider.as_mut(), request, callback);
+        1
+    } else {
+        // no provider, cancel the request
+        0
+    }
+}
+
+unsafe extern "C" fn on_render_process_terminated(
+    slf: *mut cef_request_handler_t,
+    browser: *mut cef_browser_t,
+    status: cef_termination_status_t,
+) {
+    let handler = slf as *mut RequestHandler;
+    let event = RenderProcessTerminated {
+        status: crash_recovery::termination_status(status),
+        browser_id: (*browser).get_identifier.expect("get_identifier is a function")(browser),
+        url: crash_recovery::main_frame_url(browser),
+    };
+    log::warn!("render process terminated: {:?}", event);
+
+    if let Some(render_process_terminated) = &mut (*handler).render_process_terminated {
+        render_process_terminated(&event);
+    }
+    if let Some(crash_recovery) = &mut (*handler).crash_recovery {
+        crash_recovery.recover(browser, &event);
+    }
+}
+
 pub fn allocate() -> *mut RequestHandler {
     let handler = RequestHandler {
         request_handler: cef_request_handler_t {
@@ -32,20 +109,41 @@ pub fn allocate() -> *mut RequestHandler {
             on_before_browse: None,
             on_open_urlfrom_tab: None,
             get_resource_request_handler: None,
-            get_auth_credentials: None,
+            get_auth_credentials: Some(get_auth_credentials),
             on_quota_request: None,
-            on_certificate_error: None,
+            on_certificate_error: Some(on_certificate_error),
             on_select_client_certificate: None,
             on_plugin_crashed: None,
             on_render_view_ready: None,
-            on_render_process_terminated: None,
+            on_render_process_terminated: Some(on_render_process_terminated),
         },
         ref_count: AtomicUsize::new(1),
+        certificate_policy: None,
+        credential_provider: None,
+        render_process_terminated: None,
+        crash_recovery: None,
     };
 
+    super::ref_counting::allocated("RequestHandler");
     Box::into_raw(Box::from(handler))
 }
 
+pub unsafe fn set_certificate_policy(slf: *mut RequestHandler, policy: CertificatePolicy) {
+    (*slf).certificate_policy = Some(policy);
+}
+
+pub unsafe fn set_credential_provider(slf: *mut RequestHandler, provider: Box<dyn CredentialProvider>) {
+    (*slf).credential_provider = Some(provider);
+}
+
+pub unsafe fn set_render_process_terminated_listener<F: FnMut(&RenderProcessTerminated) + 'static>(slf: *mut RequestHandler, listener: F) {
+    (*slf).render_process_terminated = Some(Box::from(listener));
+}
+
+pub unsafe fn set_crash_recovery(slf: *mut RequestHandler, crash_recovery: CrashRecovery) {
+    (*slf).crash_recovery = Some(crash_recovery);
+}
+
 extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
     let request_handler = base as *mut RequestHandler;
     unsafe {
@@ -60,6 +158,7 @@ extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
     if count == 0 {
         unsafe {
             Box::from_raw(request_handler);
+            super::ref_counting::freed("RequestHandler");
         }
         1
     } else {
@@ -1,36 +1,199 @@
-use std::sync::atomic::{AtomicI64, Ordering};
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
 
-#[derive(Debug)]
+use super::wake_handle::WakeHandle;
+
+#[derive(Debug, Default)]
+struct State {
+    /// When CEF next wants `cef_do_message_loop_work` to be called
+    deadline: Option<Instant>,
+    /// Whether someone asked the pump to wake up
+    woken: bool,
+}
+
+/// Keeps track of when CEF wants its work done, as requested through
+/// `on_schedule_message_pump_work`, which can be called from any thread
 pub struct Schedule {
-    next_work_time: AtomicI64,
-    start_time: SystemTime,
+    state: Mutex<State>,
+    condvar: Condvar,
+    wake_handle: WakeHandle,
+    wake_callback: Mutex<Option<Box<dyn Fn() + Send + Sync>>>,
+    quit: AtomicBool,
+    in_cef_loop: AtomicBool,
+    /// Wakes the tokio pump
+    #[cfg(feature = "tokio")]
+    async_wake: tokio::sync::Notify,
 }
 
 impl Schedule {
     pub fn new() -> Schedule {
         Schedule {
-            next_work_time: AtomicI64::new(0),
-            start_time: SystemTime::now(),
+            state: Mutex::new(State::default()),
+            condvar: Condvar::new(),
+            wake_handle: WakeHandle::new(),
+            wake_callback: Mutex::new(None),
+            quit: AtomicBool::new(false),
+            in_cef_loop: AtomicBool::new(false),
+            #[cfg(feature = "tokio")]
+            async_wake: tokio::sync::Notify::new(),
         }
     }
 
+    /// CEF wants work done after `delay_ms`, replacing anything scheduled
+    /// before
     pub fn schedule_work(&self, delay_ms: i64) {
-        let now = SystemTime::now();
-        let delta = now.duration_since(self.start_time).expect("can sub time");
-        let time_stamp = delta.as_millis() as i64;
-        let delay_ms = delay_ms.min(16); // wait max. 30 FPS
-        let next_time = time_stamp + delay_ms;
-        self.next_work_time.store(next_time, Ordering::SeqCst);
+        let deadline = Instant::now() + Duration::from_millis(delay_ms.max(0) as u64);
+        self.state.lock().expect("schedule isn't poisoned").deadline = Some(deadline);
+        self.notify();
+    }
+
+    /// Wake up anyone waiting for work, even if there is none
+    pub fn wake(&self) {
+        self.state.lock().expect("schedule isn't poisoned").woken = true;
+        self.notify();
+    }
+
+    fn notify(&self) {
+        self.condvar.notify_all();
+        self.wake_handle.signal();
+        #[cfg(feature = "tokio")]
+        self.async_wake.notify();
+        if let Some(wake_callback) = &*self.wake_callback.lock().expect("schedule isn't poisoned") {
+            wake_callback();
+        }
+    }
+
+    /// Called right before doing CEF's work; CEF schedules more as needed
+    pub fn work_started(&self) {
+        self.state.lock().expect("schedule isn't poisoned").deadline = None;
+        self.wake_handle.clear();
+    }
+
+    /// Forget about any wake-ups so far
+    pub fn clear_wake(&self) {
+        self.state.lock().expect("schedule isn't poisoned").woken = false;
+        self.wake_handle.clear();
     }
 
     pub fn should_do_work(&self) -> bool {
-        let next_time: i64 = self.next_work_time.load(Ordering::SeqCst);
+        let state = self.state.lock().expect("schedule isn't poisoned");
+        is_due(&state, Instant::now())
+    }
+
+    /// When CEF wants its work done, `None` if it hasn't asked
+    pub fn deadline(&self) -> Option<Instant> {
+        self.state.lock().expect("schedule isn't poisoned").deadline
+    }
+
+    /// Resolves once work is scheduled or the schedule is woken. A wake-up
+    /// that comes before this is awaited isn't lost
+    #[cfg(feature = "tokio")]
+    pub async fn woken(&self) {
+        self.async_wake.notified().await
+    }
+
+    /// How long until CEF wants its work done, `None` if it hasn't asked
+    pub fn time_until_work(&self) -> Option<Duration> {
+        let state = self.state.lock().expect("schedule isn't poisoned");
+        let now = Instant::now();
+        state.deadline.map(|deadline| deadline.saturating_duration_since(now))
+    }
+
+    /// Block until work is due, the schedule is woken, or `timeout` passes.
+    /// Returns whether work is due
+    pub fn wait_for_work(&self, timeout: Option<Duration>) -> bool {
+        let start = Instant::now();
+        let mut state = self.state.lock().expect("schedule isn't poisoned");
+        loop {
+            let now = Instant::now();
+            if state.woken {
+                state.woken = false;
+                return is_due(&state, now);
+            }
+            if is_due(&state, now) {
+                return true;
+            }
+
+            let mut wait = state.deadline.map(|deadline| deadline - now);
+            if let Some(timeout) = timeout {
+                match timeout.checked_sub(now - start) {
+                    Some(remaining) if remaining > Duration::from_secs(0) => {
+                        wait = Some(wait.map_or(remaining, |wait| wait.min(remaining)));
+                    }
+                    _ => return false,
+                }
+            }
+
+            state = match wait {
+                Some(wait) => {
+                    self.condvar
+                        .wait_timeout(state, wait)
+                        .expect("schedule isn't poisoned")
+                        .0
+                }
+                None => self.condvar.wait(state).expect("schedule isn't poisoned"),
+            };
+        }
+    }
+
+    /// Ask `Cef::run` to return
+    pub fn quit(&self) {
+        self.quit.store(true, Ordering::SeqCst);
+        self.wake();
+        if self.in_cef_loop.load(Ordering::SeqCst) {
+            // CEF's own loop can only be quit from the UI thread
+            super::task::post(
+                super::task::Thread::UI,
+                Box::from(|| unsafe { super::bindings::cef_quit_message_loop() }),
+            );
+        }
+    }
+
+    /// Whether quitting was asked for, forgetting about it
+    pub fn take_quit(&self) -> bool {
+        self.quit.swap(false, Ordering::SeqCst)
+    }
 
-        let now = SystemTime::now();
-        let delta = now.duration_since(self.start_time).expect("can sub time");
-        let time_stamp = delta.as_millis() as i64;
+    pub fn is_quitting(&self) -> bool {
+        self.quit.load(Ordering::SeqCst)
+    }
+
+    pub fn set_in_cef_loop(&self, in_cef_loop: bool) {
+        self.in_cef_loop.store(in_cef_loop, Ordering::SeqCst);
+    }
 
-        time_stamp >= next_time
+    pub fn wake_handle(&self) -> &WakeHandle {
+        &self.wake_handle
     }
-}
\ No newline at end of file
+
+    pub fn set_wake_callback(&self, callback: Box<dyn Fn() + Send + Sync>) {
+        *self.wake_callback.lock().expect("schedule isn't poisoned") = Some(callback);
+    }
+}
+
+fn is_due(state: &State, now: Instant) -> bool {
+    state.deadline.map_or(false, |deadline| deadline <= now)
+}
+
+/// Wakes up a thread blocked in `Cef::wait_for_work`, `Cef::run_until` or
+/// `Cef::run` from any other thread
+#[derive(Clone)]
+pub struct PumpWaker {
+    schedule: std::sync::Arc<Schedule>,
+}
+
+impl PumpWaker {
+    pub(crate) fn new(schedule: std::sync::Arc<Schedule>) -> PumpWaker {
+        PumpWaker { schedule }
+    }
+
+    pub fn wake(&self) {
+        self.schedule.wake();
+    }
+
+    /// Make `Cef::run` return
+    pub fn quit(&self) {
+        self.schedule.quit();
+    }
+}
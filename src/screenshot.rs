@@ -0,0 +1,147 @@
+use std::future::Future;
+
+use serde_json::{json, Value};
+
+use super::bindings::cef_browser_host_t;
+use super::client::{Client, DevToolsTarget};
+
+/// Which part of the page to capture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScreenshotMode {
+    /// Only what is currently visible in the browser
+    Viewport,
+    /// The entire scrollable page
+    FullPage,
+}
+
+/// The image format of a screenshot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScreenshotFormat {
+    Png,
+    /// JPEG with a quality from 0 to 100
+    Jpeg(u8),
+}
+
+/// A region of the page to capture, in CSS pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Options for `Browser::capture_screenshot`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreenshotOptions {
+    pub mode: ScreenshotMode,
+    /// Only capture this region; relative to the page when capturing the full
+    /// page, otherwise relative to the viewport
+    pub clip: Option<ClipRect>,
+    /// Scale the resulting image by this factor
+    pub scale: f64,
+    pub format: ScreenshotFormat,
+}
+
+impl Default for ScreenshotOptions {
+    fn default() -> ScreenshotOptions {
+        ScreenshotOptions {
+            mode: ScreenshotMode::Viewport,
+            clip: None,
+            scale: 1.0,
+            format: ScreenshotFormat::Png,
+        }
+    }
+}
+
+/// Capture a screenshot using the DevTools `Page.captureScreenshot` method,
+/// resolving to the encoded image bytes. The client and host are kept alive
+/// until the future completes
+pub unsafe fn capture(
+    client: *mut Client,
+    host: *mut cef_browser_host_t,
+    options: ScreenshotOptions,
+) -> impl Future<Output = Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>> {
+    let target = DevToolsTarget::new(client, host);
+    async move {
+        let mut params = json!({ "fromSurface": true });
+        match options.format {
+            ScreenshotFormat::Png => params["format"] = json!("png"),
+            ScreenshotFormat::Jpeg(quality) => {
+                params["format"] = json!("jpeg");
+                params["quality"] = json!(quality.min(100));
+            }
+        }
+
+        let result = match options.mode {
+            ScreenshotMode::Viewport => {
+                let clip = match options.clip {
+                    Some(clip) => Some(clip),
+                    // scaling only works with a clip, so clip to the visible area
+                    None if (options.scale - 1.0).abs() > std::f64::EPSILON => {
+                        let metrics = target.execute("Page.getLayoutMetrics", json!({})).await?;
+                        let viewport = &metrics["visualViewport"];
+                        Some(ClipRect {
+                            x: viewport["pageX"].as_f64().unwrap_or(0.0),
+                            y: viewport["pageY"].as_f64().unwrap_or(0.0),
+                            width: viewport["clientWidth"].as_f64().unwrap_or(0.0),
+                            height: viewport["clientHeight"].as_f64().unwrap_or(0.0),
+                        })
+                    }
+                    None => None,
+                };
+                if let Some(clip) = clip {
+                    params["clip"] = clip_to_json(&clip, options.scale);
+                }
+                target.execute("Page.captureScreenshot", params).await
+            }
+            ScreenshotMode::FullPage => {
+                // grow the viewport to the size of the content, capture, then put it back
+                let metrics = target.execute("Page.getLayoutMetrics", json!({})).await?;
+                let width = metrics["contentSize"]["width"].as_f64().unwrap_or(0.0).ceil();
+                let height = metrics["contentSize"]["height"].as_f64().unwrap_or(0.0).ceil();
+                target
+                    .execute(
+                        "Emulation.setDeviceMetricsOverride",
+                        json!({
+                            "width": width as i64,
+                            "height": height as i64,
+                            "deviceScaleFactor": 0,
+                            "mobile": false,
+                        }),
+                    )
+                    .await?;
+
+                let clip = options.clip.unwrap_or(ClipRect {
+                    x: 0.0,
+                    y: 0.0,
+                    width,
+                    height,
+                });
+                params["clip"] = clip_to_json(&clip, options.scale);
+                let result = target.execute("Page.captureScreenshot", params).await;
+
+                // failing to put the viewport back shouldn't lose the capture
+                if let Err(e) = target.execute("Emulation.clearDeviceMetricsOverride", json!({})).await {
+                    log::warn!("failed to restore the viewport after a screenshot: {}", e);
+                }
+                result
+            }
+        }?;
+
+        let data = result["data"]
+            .as_str()
+            .ok_or("screenshot result didn't contain any data")?;
+        Ok(base64::decode(data)?)
+    }
+}
+
+fn clip_to_json(clip: &ClipRect, scale: f64) -> Value {
+    json!({
+        "x": clip.x,
+        "y": clip.y,
+        "width": clip.width,
+        "height": clip.height,
+        "scale": scale,
+    })
+}
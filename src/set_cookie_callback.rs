@@ -0,0 +1,90 @@
+use std::mem::size_of;
+use std::os::raw::{c_int};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::bindings::{
+    cef_base_ref_counted_t, cef_set_cookie_callback_t,
+};
+
+#[repr(C)]
+pub struct SetCookieCallback {
+    set_cookie_callback: cef_set_cookie_callback_t,
+    ref_count: AtomicUsize,
+    on_done: Option<Box<dyn FnOnce(bool)>>,
+}
+
+unsafe extern "C" fn on_complete(slf: *mut cef_set_cookie_callback_t, success: c_int) {
+    let callback = slf as *mut SetCookieCallback;
+    if let Some(on_done) = (*callback).on_done.take() {
+        on_done(success == 1);
+    }
+}
+
+pub fn allocate(on_done: Option<Box<dyn FnOnce(bool)>>) -> *mut SetCookieCallback {
+    let handler = SetCookieCallback {
+        set_cookie_callback: cef_set_cookie_callback_t {
+            base: cef_base_ref_counted_t {
+                size: size_of::<SetCookieCallback>() as u64,
+                add_ref: Some(add_ref_set_cookie_callback),
+                release: Some(release_set_cookie_callback),
+                has_one_ref: Some(has_one_ref_set_cookie_callback),
+                has_at_least_one_ref: Some(has_at_least_one_ref_set_cookie_callback),
+            },
+            on_complete: Some(on_complete),
+        },
+        ref_count: AtomicUsize::new(1),
+        on_done,
+    };
+
+    super::ref_counting::allocated("SetCookieCallback");
+    Box::into_raw(Box::from(handler))
+}
+
+extern "C" fn add_ref_set_cookie_callback(base: *mut cef_base_ref_counted_t) {
+    let callback = base as *mut SetCookieCallback;
+    unsafe {
+        (*callback)
+            .ref_count
+            .fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+extern "C" fn release_set_cookie_callback(base: *mut cef_base_ref_counted_t) -> c_int {
+    let callback = base as *mut SetCookieCallback;
+    let count = unsafe {
+        (*callback)
+            .ref_count
+            .fetch_sub(1, Ordering::SeqCst)
+            - 1
+    };
+
+    if count == 0 {
+        unsafe {
+            Box::from_raw(callback);
+            super::ref_counting::freed("SetCookieCallback");
+        }
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_one_ref_set_cookie_callback(base: *mut cef_base_ref_counted_t) -> c_int {
+    let callback = base as *mut SetCookieCallback;
+    let count = unsafe { (*callback).ref_count.load(Ordering::SeqCst) };
+    if count == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_at_least_one_ref_set_cookie_callback(base: *mut cef_base_ref_counted_t) -> c_int {
+    let callback = base as *mut SetCookieCallback;
+    let count = unsafe { (*callback).ref_count.load(Ordering::SeqCst) };
+    if count >= 1 {
+        1
+    } else {
+        0
+    }
+}
+
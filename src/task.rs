@@ -0,0 +1,144 @@
+use std::mem::size_of;
+use std::os::raw::{c_int};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use super::bindings::{
+    cef_base_ref_counted_t, cef_currently_on, cef_post_delayed_task, cef_post_task, cef_task_t,
+    cef_thread_id_t, cef_thread_id_t_TID_FILE_BACKGROUND, cef_thread_id_t_TID_FILE_USER_BLOCKING,
+    cef_thread_id_t_TID_FILE_USER_VISIBLE, cef_thread_id_t_TID_IO,
+    cef_thread_id_t_TID_PROCESS_LAUNCHER, cef_thread_id_t_TID_RENDERER, cef_thread_id_t_TID_UI,
+};
+
+/// The named CEF threads that tasks can be posted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Thread {
+    /// The browser process' main thread; browsers may only be used here
+    UI,
+    /// For blocking work that the user won't notice, i.e. writing logs
+    FileBackground,
+    /// For blocking work whose result the user will see eventually
+    FileUserVisible,
+    /// For blocking work the user is actively waiting on
+    FileUserBlocking,
+    /// Launches and terminates child processes
+    ProcessLauncher,
+    /// Handles IPC and network messages; don't block it
+    IO,
+    /// The render process' main thread
+    Renderer,
+}
+
+impl Thread {
+    fn to_cef(self) -> cef_thread_id_t {
+        match self {
+            Thread::UI => cef_thread_id_t_TID_UI,
+            Thread::FileBackground => cef_thread_id_t_TID_FILE_BACKGROUND,
+            Thread::FileUserVisible => cef_thread_id_t_TID_FILE_USER_VISIBLE,
+            Thread::FileUserBlocking => cef_thread_id_t_TID_FILE_USER_BLOCKING,
+            Thread::ProcessLauncher => cef_thread_id_t_TID_PROCESS_LAUNCHER,
+            Thread::IO => cef_thread_id_t_TID_IO,
+            Thread::Renderer => cef_thread_id_t_TID_RENDERER,
+        }
+    }
+}
+
+#[repr(C)]
+pub struct Task {
+    task: cef_task_t,
+    ref_count: AtomicUsize,
+    work: Option<Box<dyn FnOnce() + Send>>,
+}
+
+unsafe extern "C" fn execute(slf: *mut cef_task_t) {
+    let task = slf as *mut Task;
+    if let Some(work) = (*task).work.take() {
+        work();
+    }
+}
+
+pub fn allocate(work: Box<dyn FnOnce() + Send>) -> *mut Task {
+    let task = Task {
+        task: cef_task_t {
+            base: cef_base_ref_counted_t {
+                size: size_of::<Task>() as u64,
+                add_ref: Some(add_ref_task),
+                release: Some(release_task),
+                has_one_ref: Some(has_one_ref_task),
+                has_at_least_one_ref: Some(has_at_least_one_ref_task),
+            },
+            execute: Some(execute),
+        },
+        ref_count: AtomicUsize::new(1),
+        work: Some(work),
+    };
+
+    super::ref_counting::allocated("Task");
+    Box::into_raw(Box::from(task))
+}
+
+/// Run `work` on the given thread as soon as possible. Returns `false` if
+/// the task couldn't be posted, i.e. because CEF is shutting down
+pub fn post(thread: Thread, work: Box<dyn FnOnce() + Send>) -> bool {
+    let task = allocate(work);
+    unsafe { cef_post_task(thread.to_cef(), task as *mut cef_task_t) == 1 }
+}
+
+/// Run `work` on the given thread once `delay` has passed
+pub fn post_delayed(thread: Thread, delay: Duration, work: Box<dyn FnOnce() + Send>) -> bool {
+    let task = allocate(work);
+    unsafe { cef_post_delayed_task(thread.to_cef(), task as *mut cef_task_t, delay.as_millis() as i64) == 1 }
+}
+
+pub fn currently_on(thread: Thread) -> bool {
+    unsafe { cef_currently_on(thread.to_cef()) == 1 }
+}
+
+extern "C" fn add_ref_task(base: *mut cef_base_ref_counted_t) {
+    let task = base as *mut Task;
+    unsafe {
+        (*task)
+            .ref_count
+            .fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+extern "C" fn release_task(base: *mut cef_base_ref_counted_t) -> c_int {
+    let task = base as *mut Task;
+    let count = unsafe {
+        (*task)
+            .ref_count
+            .fetch_sub(1, Ordering::SeqCst)
+            - 1
+    };
+
+    if count == 0 {
+        unsafe {
+            Box::from_raw(task);
+            super::ref_counting::freed("Task");
+        }
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_one_ref_task(base: *mut cef_base_ref_counted_t) -> c_int {
+    let task = base as *mut Task;
+    let count = unsafe { (*task).ref_count.load(Ordering::SeqCst) };
+    if count == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_at_least_one_ref_task(base: *mut cef_base_ref_counted_t) -> c_int {
+    let task = base as *mut Task;
+    let count = unsafe { (*task).ref_count.load(Ordering::SeqCst) };
+    if count >= 1 {
+        1
+    } else {
+        0
+    }
+}
@@ -0,0 +1,192 @@
+use std::mem::size_of;
+use std::os::raw::{c_int};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::bindings::{
+    cef_base_ref_counted_t, cef_v8handler_t, cef_string_t, cef_v8value_t, size_t,
+    cef_string_userfree_t, cef_process_message_t,
+    cef_v8context_get_current_context, cef_v8value_create_bool, cef_v8value_create_string,
+};
+use super::value::to_cef_string;
+
+#[repr(C)]
+pub struct V8DragDropHandler {
+    v8_handler: cef_v8handler_t,
+    ref_count: AtomicUsize,
+    /// The paths the browser process sent when the drag entered, as a JSON array
+    pub dragged_files: Option<String>,
+}
+
+// the browser process sends the paths of dragged files in a `dragged_files`
+// message when the drag enters; once they're dropped we stop Chromium from
+// navigating to them and hand the paths to our listeners. The native
+// functions are declared inside the closure so page script can't reach them
+const CODE: &str = r#"
+    var cef;
+    if(!cef) cef = {};
+    (function() {
+        native function hasDraggedFiles();
+        native function takeDraggedFiles();
+        var listeners = [];
+        cef.onFilesDropped = function(listener) {
+            listeners.push(listener);
+        };
+        window.addEventListener("dragover", function(e) {
+            if(hasDraggedFiles()) e.preventDefault();
+        }, true);
+        window.addEventListener("drop", function(e) {
+            if(!hasDraggedFiles()) return;
+            e.preventDefault();
+            var paths = JSON.parse(takeDraggedFiles());
+            listeners.forEach(function(listener) { listener(paths); });
+        }, true);
+    })();
+"#;
+
+pub unsafe fn register_extension(extension: *mut V8DragDropHandler) {
+    use std::ffi::CString;
+    use super::bindings::{cef_string_utf8_to_utf16, cef_register_extension};
+    let code = CODE.as_bytes();
+    let code = CString::new(code).unwrap();
+    let mut cef_code = cef_string_t::default();
+    cef_string_utf8_to_utf16(code.as_ptr(), code.to_bytes().len() as u64, &mut cef_code);
+
+    let extension_name = "CEF Drag and Drop";
+    let extension_name = extension_name.as_bytes();
+    let extension_name = CString::new(extension_name).unwrap();
+    let mut cef_extension_name = cef_string_t::default();
+    cef_string_utf8_to_utf16(extension_name.as_ptr(), extension_name.to_bytes().len() as u64, &mut cef_extension_name);
+
+    // registering hands a reference over to CEF, keep ours
+    super::ref_counting::add_ref(extension);
+    cef_register_extension(&cef_extension_name, &cef_code, extension as *mut cef_v8handler_t);
+    log::debug!("registered drag and drop extension");
+}
+
+pub unsafe fn process_message(slf: *mut V8DragDropHandler, message_name: &str, message: *mut cef_process_message_t) -> bool {
+    if message_name != "dragged_files" {
+        return false;
+    }
+
+    // the paths come as a JSON array, which the JS side parses. An empty
+    // array clears out the paths of an earlier drag
+    let args = ((*message).get_argument_list.expect("get_argument_list is a function"))(message);
+    let cef_paths: cef_string_userfree_t = (*args).get_string.expect("get_string is a function")(args, 0);
+    let paths = super::value::from_cef_userfree(cef_paths);
+    (*slf).dragged_files = if paths.is_empty() || paths == "[]" { None } else { Some(paths) };
+
+    true
+}
+
+/// Whether the script calling us runs in the main frame, which is the only
+/// frame the browser process sends dragged files to
+unsafe fn in_main_frame() -> bool {
+    let context = cef_v8context_get_current_context();
+    if context.is_null() {
+        return false;
+    }
+    let frame = ((*context).get_frame.expect("get_frame is a function"))(context);
+    super::ref_counting::release(context);
+    if frame.is_null() {
+        return false;
+    }
+    let is_main = ((*frame).is_main.expect("is_main is a function"))(frame) == 1;
+    super::ref_counting::release(frame);
+    is_main
+}
+
+unsafe extern "C" fn execute(
+    slf: *mut cef_v8handler_t,
+    name: *const cef_string_t,
+    _object: *mut cef_v8value_t,
+    arguments_count: size_t,
+    _arguments: *const *mut cef_v8value_t,
+    retval: *mut *mut cef_v8value_t,
+    _exception: *mut cef_string_t,
+) -> c_int {
+    // get the name of the function
+    let chars: *mut u16 = (*name).str;
+    let len: usize = (*name).length as usize;
+    let chars = std::slice::from_raw_parts(chars, len);
+    let name = std::char::decode_utf16(chars.iter().cloned())
+        .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+        .collect::<String>();
+
+    let _self = slf as *mut V8DragDropHandler;
+    if name == "hasDraggedFiles" && arguments_count == 0 {
+        let has_files = (*_self).dragged_files.is_some() && in_main_frame();
+        *retval = cef_v8value_create_bool(has_files as c_int);
+        1
+    }
+    else if name == "takeDraggedFiles" && arguments_count == 0 {
+        let paths = if in_main_frame() { (*_self).dragged_files.take() } else { None };
+        let mut cef_paths = to_cef_string(paths.as_deref().unwrap_or("[]"));
+        *retval = cef_v8value_create_string(&cef_paths);
+        super::bindings::cef_string_utf16_clear(&mut cef_paths);
+        1
+    }
+    else {
+        log::warn!("unrecognized function: `{}` with {} args, skipping", name, arguments_count);
+        0
+    }
+}
+
+pub fn allocate() -> *mut V8DragDropHandler {
+    let handler = V8DragDropHandler {
+        v8_handler: cef_v8handler_t {
+            base: cef_base_ref_counted_t {
+                size: size_of::<V8DragDropHandler>() as u64,
+                add_ref: Some(add_ref),
+                release: Some(release),
+                has_one_ref: Some(has_one_ref),
+                has_at_least_one_ref: Some(has_at_least_one_ref),
+            },
+            execute: Some(execute),
+        },
+        ref_count: AtomicUsize::new(1),
+        dragged_files: None,
+    };
+
+    super::ref_counting::allocated("V8DragDropHandler");
+    Box::into_raw(Box::from(handler))
+}
+
+extern "C" fn add_ref(base: *mut cef_base_ref_counted_t) {
+    let v8_handler = base as *mut V8DragDropHandler;
+    unsafe { (*v8_handler).ref_count.fetch_add(1, Ordering::SeqCst) };
+}
+
+extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
+    let v8_handler = base as *mut V8DragDropHandler;
+    let count = unsafe { (*v8_handler).ref_count.fetch_sub(1, Ordering::SeqCst) - 1 };
+
+    if count == 0 {
+        unsafe {
+            Box::from_raw(v8_handler);
+            super::ref_counting::freed("V8DragDropHandler");
+        }
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let v8_handler = base as *mut V8DragDropHandler;
+    let count = unsafe { (*v8_handler).ref_count.load(Ordering::SeqCst) };
+    if count == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+extern "C" fn has_at_least_one_ref(base: *mut cef_base_ref_counted_t) -> c_int {
+    let v8_handler = base as *mut V8DragDropHandler;
+    let count = unsafe { (*v8_handler).ref_count.load(Ordering::SeqCst) };
+    if count >= 1 {
+        1
+    } else {
+        0
+    }
+}
@@ -8,11 +8,6 @@ use super::bindings::{
     cef_v8context_get_current_context, cef_process_message_t, cef_v8value_create_string
 };
 
-pub enum FileDialogMode {
-    Open,
-    Save,
-}
-
 #[repr(C)]
 pub struct V8FileDialogHandler {
     v8_handler: cef_v8handler_t,
@@ -25,17 +20,33 @@ const CODE: &str = r#"
     var cef;
     if(!cef) cef = {};
     (function() {
-        cef.saveFileDialog = function(title, defaultFileName, filter) {
-            native function saveFileDialog(title, defaultFileName, filter, onDone, onError);
+        function run(options) {
+            native function runFileDialog(options, onDone, onError);
             return new Promise((resolve, reject) => {
-                saveFileDialog(title, defaultFileName, filter, resolve, reject);
+                runFileDialog(JSON.stringify(options), paths => resolve(JSON.parse(paths)), reject);
             });
+        }
+        // the original (title, defaultFileName, filter) form resolves to a
+        // single path and rejects when cancelled
+        function runSingle(mode, title, defaultFileName, filter) {
+            return run({
+                mode: mode,
+                title: title || "",
+                defaultPath: defaultFileName || "",
+                filters: filter ? [filter] : [],
+            }).then(paths => paths.length > 0 ? paths[0] : Promise.reject());
+        }
+        cef.openFileDialog = function(options) {
+            if(typeof options === "object" && options !== null) {
+                return run(options);
+            }
+            return runSingle("open", arguments[0], arguments[1], arguments[2]);
         };
-        cef.openFileDialog = function(title, defaultFileName, filter) {
-            native function openFileDialog(title, defaultFileName, filter, onDone, onError);
-            return new Promise((resolve, reject) => {
-                openFileDialog(title, defaultFileName, filter, resolve, reject);
-            });
+        cef.saveFileDialog = function(options) {
+            if(typeof options === "object" && options !== null) {
+                return run(Object.assign({ mode: "save" }, options));
+            }
+            return runSingle("save", arguments[0], arguments[1], arguments[2]);
         };
     })();
 "#;
@@ -54,6 +65,8 @@ pub unsafe fn register_extension(extension: *mut V8FileDialogHandler) {
     let mut cef_extension_name = cef_string_t::default();
     cef_string_utf8_to_utf16(extension_name.as_ptr(), extension_name.to_bytes().len() as u64, &mut cef_extension_name);
 
+    // registering hands a reference over to CEF, keep ours
+    super::ref_counting::add_ref(extension);
     cef_register_extension(&cef_extension_name, &cef_code, extension as *mut cef_v8handler_t);
     log::debug!("registered file dialogs extension");
 }
@@ -63,37 +76,26 @@ pub unsafe fn process_message(slf: *mut V8FileDialogHandler, message_name: &str,
         return false;
     }
 
+    // the paths come as a JSON array, which the JS side parses
     let args = ((*message).get_argument_list.expect("get_argument_list is a function"))(message);
-    let size = (*args).get_size.expect("get_size is a function")(args);
-    if size < 1 {
-        on_file_dialog_done(slf, None);
-    }
-    else {
-        let cef_path: cef_string_userfree_t = (*args).get_string.expect("get_string is a function")(args, 0);
-        on_file_dialog_done(slf, Some(cef_path));
-        cef_string_userfree_utf16_free(cef_path);
-    }
+    let cef_paths: cef_string_userfree_t = (*args).get_string.expect("get_string is a function")(args, 0);
+    on_file_dialog_done(slf, cef_paths);
+    cef_string_userfree_utf16_free(cef_paths);
 
     true
 }
 
-unsafe fn on_file_dialog_done(slf: *mut V8FileDialogHandler, path: Option<*const cef_string_t>) {
-    if let Some((context, on_success, on_error)) = (*slf).done_callback {
+unsafe fn on_file_dialog_done(slf: *mut V8FileDialogHandler, paths: *const cef_string_t) {
+    if let Some((context, on_success, _on_error)) = (*slf).done_callback.take() {
         ((*context).enter.expect("enter is a function"))(context);
 
-        if let Some(path) = path {
-            // store the path as a string in v8
-            let v8_path = cef_v8value_create_string(path);
-            // and call success, with a single argument that is the path!
-            ((*on_success).execute_function.expect("execute_function is a function"))(on_success, std::ptr::null_mut(), 1, &v8_path);
-        }
-        else {
-            // if the path was None, the user cancelled; report that as an error to JS
-            ((*on_error).execute_function.expect("execute_function is a function"))(on_error, std::ptr::null_mut(), 0, std::ptr::null_mut());
-        }
+        // store the paths as a string in v8 and call success with it
+        let empty = cef_string_t::default();
+        let v8_paths = cef_v8value_create_string(if paths.is_null() { &empty } else { paths });
+        ((*on_success).execute_function.expect("execute_function is a function"))(on_success, std::ptr::null_mut(), 1, &v8_paths);
+        super::ref_counting::release(v8_paths);
 
         ((*context).exit.expect("exit is a function"))(context);
-        (*slf).done_callback = None;
     }
     else {
         log::warn!("file dialog is done but callback wasn't set?!");
@@ -117,34 +119,17 @@ unsafe extern "C" fn execute(
         .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
         .collect::<String>();
 
-    if (name == "saveFileDialog" || name == "openFileDialog") && arguments_count == 5 {
-        // get the title argument
-        let arg_title: *mut cef_v8value_t = *arguments.offset(0);
-        let is_string = ((*arg_title).is_string.expect("is_string is a function"))(arg_title) == 1;
+    if name == "runFileDialog" && arguments_count == 3 {
+        // get the options argument, already stringified to JSON
+        let arg_options: *mut cef_v8value_t = *arguments.offset(0);
+        let is_string = ((*arg_options).is_string.expect("is_string is a function"))(arg_options) == 1;
         if !is_string {
-            log::warn!("title argument isn't a string!");
-            return 0;
-        }
-
-        // get the file name argument
-        let arg_file_name: *mut cef_v8value_t = *arguments.offset(1);
-        let is_string: bool = ((*arg_file_name).is_string.expect("is_string is a function"))(arg_file_name) == 1;
-        let arg_file_name_is_null: bool = ((*arg_file_name).is_null.expect("is_null is a function"))(arg_file_name) == 1;
-        if !arg_file_name_is_null && !is_string {
-            log::error!("file name argument isn't a string, nor is it null!");
-            return 0;
-        }
-
-        // get the filter argument
-        let arg_filter: *mut cef_v8value_t = *arguments.offset(2);
-        let is_string = ((*arg_filter).is_string.expect("is_string is a function"))(arg_filter) == 1;
-        if !is_string {
-            log::warn!("filter argument isn't a string!");
+            log::warn!("options argument isn't a string!");
             return 0;
         }
 
         // get the onDone argument
-        let arg_on_done: *mut cef_v8value_t = *arguments.offset(3);
+        let arg_on_done: *mut cef_v8value_t = *arguments.offset(1);
         let is_function = ((*arg_on_done).is_function.expect("is_function is a function"))(arg_on_done) == 1;
         if !is_function {
             log::warn!("onDone argument isn't a function!");
@@ -152,33 +137,21 @@ unsafe extern "C" fn execute(
         }
 
         // get the onError argument
-        let arg_on_error: *mut cef_v8value_t = *arguments.offset(4);
+        let arg_on_error: *mut cef_v8value_t = *arguments.offset(2);
         let is_function = ((*arg_on_error).is_function.expect("is_function is a function"))(arg_on_error) == 1;
         if !is_function {
             log::warn!("onError argument isn't a function!");
             return 0;
         }
 
-        // get the v8 strings as cef strings
-        let cef_title: cef_string_userfree_t = ((*arg_title).get_string_value.expect("get_string_value is a function"))(arg_title);
-        let cef_file_name: cef_string_userfree_t = if arg_file_name_is_null {
-            super::bindings::cef_string_userfree_utf16_alloc()
-        }
-        else {
-            ((*arg_file_name).get_string_value.expect("get_string_value is a function"))(arg_file_name)
-        };
-        let cef_filter: cef_string_userfree_t = ((*arg_filter).get_string_value.expect("get_string_value is a function"))(arg_filter);
+        let cef_options: cef_string_userfree_t = ((*arg_options).get_string_value.expect("get_string_value is a function"))(arg_options);
 
-        // now send an IPC message to the frame process telling it to print
+        // now send an IPC message to the browser process telling it to show the dialog
         let _self = slf as *mut V8FileDialogHandler;
         if let Some(frame) = (*_self).frame {
             // convert the message name to a CEF string
             let mut cef_message_name = cef_string_t::default();
-            let message_name = match name.as_ref() {
-                "openFileDialog" => "open_file_dialog".as_bytes(),
-                "saveFileDialog" => "save_file_dialog".as_bytes(),
-                _ => unreachable!(),
-            };
+            let message_name = "run_file_dialog".as_bytes();
             let message_name = std::ffi::CString::new(message_name).unwrap();
             super::bindings::cef_string_utf8_to_utf16(message_name.as_ptr(), message_name.to_bytes().len() as u64, &mut cef_message_name);
 
@@ -189,21 +162,18 @@ unsafe extern "C" fn execute(
             // build the message
             let message = super::bindings::cef_process_message_create(&cef_message_name);
             let args = ((*message).get_argument_list.expect("get_argument_list is a function"))(message);
-            ((*args).set_size.expect("set_size is a function"))(args, 3);
-            ((*args).set_string.expect("set_string is a function"))(args, 0, cef_title);
-            ((*args).set_string.expect("set_string is a function"))(args, 1, cef_file_name);
-            ((*args).set_string.expect("set_string is a function"))(args, 2, cef_filter);
+            ((*args).set_size.expect("set_size is a function"))(args, 1);
+            ((*args).set_string.expect("set_string is a function"))(args, 0, cef_options);
 
             // send the message
             ((*frame).send_process_message.expect("send_process_message is a function"))(frame, super::bindings::cef_process_id_t_PID_BROWSER, message);
+            super::bindings::cef_string_utf16_clear(&mut cef_message_name);
         }
         else {
             log::error!("frame isn't set!");
         }
 
-        cef_string_userfree_utf16_free(cef_title);
-        cef_string_userfree_utf16_free(cef_file_name);
-        cef_string_userfree_utf16_free(cef_filter);
+        cef_string_userfree_utf16_free(cef_options);
         1
     }
     else {
@@ -229,6 +199,7 @@ pub fn allocate() -> *mut V8FileDialogHandler {
         done_callback: None,
     };
 
+    super::ref_counting::allocated("V8FileDialogHandler");
     Box::into_raw(Box::from(handler))
 }
 
@@ -243,7 +214,11 @@ extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
 
     if count == 0 {
         unsafe {
-            Box::from_raw(v8_handler);
+            let handler: Box<V8FileDialogHandler> = Box::from_raw(v8_handler);
+            super::ref_counting::freed("V8FileDialogHandler");
+            if let Some(frame) = handler.frame {
+                super::ref_counting::release(frame);
+            }
         }
         1
     } else {
@@ -5,7 +5,7 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use super::bindings::{
     cef_base_ref_counted_t, cef_v8handler_t, cef_string_t, cef_v8value_t, size_t,
     cef_string_userfree_t, cef_string_userfree_utf16_free, cef_frame_t, cef_v8context_t,
-    cef_v8context_get_current_context, cef_process_message_t
+    cef_v8context_get_current_context, cef_process_message_t, cef_v8value_create_string
 };
 
 #[repr(C)]
@@ -14,16 +14,30 @@ pub struct V8PDFPrintHandler {
     ref_count: AtomicUsize,
     pub frame: Option<*mut cef_frame_t>,
     pub done_callback: Option<(*mut cef_v8context_t, *mut cef_v8value_t, *mut cef_v8value_t)>,
+    pub data_callback: Option<(*mut cef_v8context_t, *mut cef_v8value_t, *mut cef_v8value_t)>,
 }
 
 const CODE: &str = r#"
     var cef;
     if(!cef) cef = {};
     (function() {
-        cef.printToPDF = function(path) {
-            native function printToPDF(path, onDone, onError);
+        cef.printToPDF = function(path, options) {
+            native function printToPDF(path, options, onDone, onError);
             return new Promise((resolve, reject) => {
-                printToPDF(path, resolve, reject);
+                printToPDF(path, JSON.stringify(options || {}), resolve, reject);
+            });
+        };
+        cef.printToPDFData = function(options) {
+            native function printToPDFData(options, onDone, onError);
+            return new Promise((resolve, reject) => {
+                printToPDFData(JSON.stringify(options || {}), data => {
+                    const bytes = atob(data);
+                    const buffer = new Uint8Array(bytes.length);
+                    for(let i = 0; i < bytes.length; i++) {
+                        buffer[i] = bytes.charCodeAt(i);
+                    }
+                    resolve(buffer.buffer);
+                }, error => reject(new Error(error)));
             });
         };
     })();
@@ -43,19 +57,48 @@ pub unsafe fn register_extension(extension: *mut V8PDFPrintHandler) {
     let mut cef_extension_name = cef_string_t::default();
     cef_string_utf8_to_utf16(extension_name.as_ptr(), extension_name.to_bytes().len() as u64, &mut cef_extension_name);
 
+    // registering hands a reference over to CEF, keep ours
+    super::ref_counting::add_ref(extension);
     cef_register_extension(&cef_extension_name, &cef_code, extension as *mut cef_v8handler_t);
     log::debug!("registered pdf printer extension");
 }
 
 pub unsafe fn process_message(slf: *mut V8PDFPrintHandler, message_name: &str, message: *mut cef_process_message_t) -> bool {
-    if message_name != "print_to_pdf_done" {
-        return false;
+    match message_name {
+        "print_to_pdf_done" => {
+            let args = ((*message).get_argument_list.expect("get_argument_list is a function"))(message);
+            let ok: bool = ((*args).get_bool.expect("get_bool is a function"))(args, 0) == 1;
+            on_pdf_print_done(slf, ok);
+            true
+        }
+        "print_to_pdf_data_done" => {
+            let args = ((*message).get_argument_list.expect("get_argument_list is a function"))(message);
+            let ok: bool = ((*args).get_bool.expect("get_bool is a function"))(args, 0) == 1;
+            let payload = ((*args).get_string.expect("get_string is a function"))(args, 1);
+            on_pdf_data_done(slf, ok, payload);
+            cef_string_userfree_utf16_free(payload);
+            true
+        }
+        _ => false,
     }
+}
+
+/// Hand the base64 encoded PDF, or the error, to the waiting promise
+unsafe fn on_pdf_data_done(slf: *mut V8PDFPrintHandler, ok: bool, payload: cef_string_userfree_t) {
+    if let Some((context, on_success, on_error)) = (*slf).data_callback.take() {
+        ((*context).enter.expect("enter is a function"))(context);
 
-    let args = ((*message).get_argument_list.expect("get_argument_list is a function"))(message);
-    let ok: bool = ((*args).get_bool.expect("get_bool is a function"))(args, 0) == 1;
-    on_pdf_print_done(slf, ok);
-    true
+        let empty = cef_string_t::default();
+        let payload = cef_v8value_create_string(if payload.is_null() { &empty } else { payload });
+        let callback = if ok { on_success } else { on_error };
+        ((*callback).execute_function.expect("execute_function is a function"))(callback, std::ptr::null_mut(), 1, &payload);
+        super::ref_counting::release(payload);
+
+        ((*context).exit.expect("exit is a function"))(context);
+    }
+    else {
+        log::warn!("pdf data is done but callback wasn't set?!");
+    }
 }
 
 unsafe fn on_pdf_print_done(slf: *mut V8PDFPrintHandler, ok: bool) {
@@ -95,7 +138,7 @@ unsafe extern "C" fn execute(
         .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
         .collect::<String>();
 
-    if name == "printToPDF" && arguments_count == 3 {
+    if name == "printToPDF" && arguments_count == 4 {
         log::debug!("printing!");
 
         // get the path argument
@@ -106,8 +149,16 @@ unsafe extern "C" fn execute(
             return 0;
         }
 
+        // get the options argument, already stringified to JSON
+        let arg_options: *mut cef_v8value_t = *(arguments.offset(1));
+        let is_string = ((*arg_options).is_string.expect("is_string is a function"))(arg_options) == 1;
+        if !is_string {
+            log::warn!("options argument isn't a string!");
+            return 0;
+        }
+
         // get the onDone argument
-        let arg_on_done: *mut cef_v8value_t = *(arguments.offset(1));
+        let arg_on_done: *mut cef_v8value_t = *(arguments.offset(2));
         let is_function = ((*arg_on_done).is_function.expect("is_function is a function"))(arg_on_done) == 1;
         if !is_function {
             log::warn!("onDone argument isn't a function!");
@@ -115,7 +166,7 @@ unsafe extern "C" fn execute(
         }
 
         // get the onError argument
-        let arg_on_error: *mut cef_v8value_t = *(arguments.offset(2));
+        let arg_on_error: *mut cef_v8value_t = *(arguments.offset(3));
         let is_function = ((*arg_on_error).is_function.expect("is_function is a function"))(arg_on_error) == 1;
         if !is_function {
             log::warn!("onError argument isn't a function!");
@@ -124,6 +175,7 @@ unsafe extern "C" fn execute(
 
         // get the path as a string
         let cef_path: cef_string_userfree_t = ((*arg_path).get_string_value.expect("get_string_value is a function"))(arg_path);
+        let cef_options: cef_string_userfree_t = ((*arg_options).get_string_value.expect("get_string_value is a function"))(arg_options);
 
         // now send an IPC message to the frame process telling it to print
         let _self = slf as *mut V8PDFPrintHandler;
@@ -141,8 +193,9 @@ unsafe extern "C" fn execute(
             // build the message
             let message = super::bindings::cef_process_message_create(&cef_message_name);
             let args = ((*message).get_argument_list.expect("get_argument_list is a function"))(message);
-            ((*args).set_size.expect("set_size is a function"))(args, 1);
+            ((*args).set_size.expect("set_size is a function"))(args, 2);
             ((*args).set_string.expect("set_string is a function"))(args, 0, cef_path);
+            ((*args).set_string.expect("set_string is a function"))(args, 1, cef_options);
 
             // send the message
             ((*frame).send_process_message.expect("send_process_message is a function"))(frame, super::bindings::cef_process_id_t_PID_BROWSER, message);
@@ -152,6 +205,54 @@ unsafe extern "C" fn execute(
         }
 
         cef_string_userfree_utf16_free(cef_path);
+        cef_string_userfree_utf16_free(cef_options);
+        1
+    }
+    else if name == "printToPDFData" && arguments_count == 3 {
+        // get the options argument, already stringified to JSON
+        let arg_options: *mut cef_v8value_t = *arguments;
+        let is_string = ((*arg_options).is_string.expect("is_string is a function"))(arg_options) == 1;
+        if !is_string {
+            log::warn!("options argument isn't a string!");
+            return 0;
+        }
+
+        // get the onDone and onError arguments
+        let arg_on_done: *mut cef_v8value_t = *(arguments.offset(1));
+        let arg_on_error: *mut cef_v8value_t = *(arguments.offset(2));
+        let are_functions = ((*arg_on_done).is_function.expect("is_function is a function"))(arg_on_done) == 1
+            && ((*arg_on_error).is_function.expect("is_function is a function"))(arg_on_error) == 1;
+        if !are_functions {
+            log::warn!("onDone or onError argument isn't a function!");
+            return 0;
+        }
+
+        let cef_options: cef_string_userfree_t = ((*arg_options).get_string_value.expect("get_string_value is a function"))(arg_options);
+
+        // now ask the browser process to print into memory
+        let _self = slf as *mut V8PDFPrintHandler;
+        if let Some(frame) = (*_self).frame {
+            let mut cef_message_name = cef_string_t::default();
+            let message_name = "print_to_pdf_data".as_bytes();
+            let message_name = std::ffi::CString::new(message_name).unwrap();
+            super::bindings::cef_string_utf8_to_utf16(message_name.as_ptr(), message_name.to_bytes().len() as u64, &mut cef_message_name);
+
+            let context = cef_v8context_get_current_context();
+            (*_self).data_callback = Some((context, arg_on_done, arg_on_error));
+
+            let message = super::bindings::cef_process_message_create(&cef_message_name);
+            let args = ((*message).get_argument_list.expect("get_argument_list is a function"))(message);
+            ((*args).set_size.expect("set_size is a function"))(args, 1);
+            ((*args).set_string.expect("set_string is a function"))(args, 0, cef_options);
+
+            ((*frame).send_process_message.expect("send_process_message is a function"))(frame, super::bindings::cef_process_id_t_PID_BROWSER, message);
+            super::bindings::cef_string_utf16_clear(&mut cef_message_name);
+        }
+        else {
+            log::error!("frame isn't set!");
+        }
+
+        cef_string_userfree_utf16_free(cef_options);
         1
     }
     else {
@@ -175,8 +276,10 @@ pub fn allocate() -> *mut V8PDFPrintHandler {
         ref_count: AtomicUsize::new(1),
         frame: None,
         done_callback: None,
+        data_callback: None,
     };
 
+    super::ref_counting::allocated("V8PDFPrintHandler");
     Box::into_raw(Box::from(handler))
 }
 
@@ -191,7 +294,11 @@ extern "C" fn release(base: *mut cef_base_ref_counted_t) -> c_int {
 
     if count == 0 {
         unsafe {
-            Box::from_raw(v8_handler);
+            let handler: Box<V8PDFPrintHandler> = Box::from_raw(v8_handler);
+            super::ref_counting::freed("V8PDFPrintHandler");
+            if let Some(frame) = handler.frame {
+                super::ref_counting::release(frame);
+            }
         }
         1
     } else {
@@ -0,0 +1,234 @@
+use std::os::raw::c_char;
+use std::ptr::null_mut;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{Map, Number, Value};
+
+use super::bindings::{
+    cef_dictionary_value_create, cef_dictionary_value_t, cef_list_value_create, cef_list_value_t,
+    cef_string_list_alloc, cef_string_list_free, cef_string_list_size, cef_string_list_t,
+    cef_string_list_value, cef_string_t, cef_string_userfree_t, cef_string_userfree_utf16_free,
+    cef_string_utf16_clear, cef_string_utf8_to_utf16, cef_time_from_timet, cef_time_t,
+    cef_time_to_timet, cef_value_create, cef_value_t, cef_value_type_t_VTYPE_BOOL,
+    cef_value_type_t_VTYPE_DICTIONARY, cef_value_type_t_VTYPE_DOUBLE, cef_value_type_t_VTYPE_INT,
+    cef_value_type_t_VTYPE_LIST, cef_value_type_t_VTYPE_STRING, time_t,
+};
+
+/// Convert a rust string into a CEF string, the result must be cleared with
+/// `cef_string_utf16_clear` once CEF is done with it
+pub unsafe fn to_cef_string(s: &str) -> cef_string_t {
+    let mut cef_s = cef_string_t::default();
+    cef_string_utf8_to_utf16(s.as_ptr() as *const c_char, s.len() as u64, &mut cef_s);
+    cef_s
+}
+
+/// Read a CEF string into a rust string
+pub unsafe fn from_cef_string(s: *const cef_string_t) -> String {
+    if s.is_null() || (*s).str.is_null() {
+        return String::new();
+    }
+    let chars = std::slice::from_raw_parts((*s).str, (*s).length as usize);
+    std::char::decode_utf16(chars.iter().cloned())
+        .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+        .collect::<String>()
+}
+
+/// Read a userfree CEF string into a rust string, freeing the CEF string
+pub unsafe fn from_cef_userfree(s: cef_string_userfree_t) -> String {
+    if s.is_null() {
+        return String::new();
+    }
+    let value = from_cef_string(s);
+    cef_string_userfree_utf16_free(s);
+    value
+}
+
+/// Read every string out of a CEF string list
+pub unsafe fn from_cef_string_list(list: cef_string_list_t) -> Vec<String> {
+    if list.is_null() {
+        return Vec::new();
+    }
+    let size = cef_string_list_size(list);
+    let mut strings: Vec<String> = Vec::with_capacity(size as usize);
+    for i in 0..size {
+        let mut cef_s = cef_string_t::default();
+        if cef_string_list_value(list, i, &mut cef_s) == 1 {
+            strings.push(from_cef_string(&cef_s));
+            cef_string_utf16_clear(&mut cef_s);
+        }
+    }
+    strings
+}
+
+/// Convert a CEF time into a system time, if it's valid
+pub unsafe fn from_cef_time(time: &cef_time_t) -> Option<SystemTime> {
+    let mut seconds: time_t = 0;
+    if cef_time_to_timet(time, &mut seconds) != 1 {
+        return None;
+    }
+    if seconds >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(seconds as u64))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(-seconds as u64))
+    }
+}
+
+/// Convert a system time into a CEF time
+pub unsafe fn to_cef_time(time: SystemTime) -> cef_time_t {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as time_t,
+        Err(e) => -(e.duration().as_secs() as time_t),
+    };
+    let mut cef_time = cef_time_t::default();
+    cef_time_from_timet(seconds, &mut cef_time);
+    cef_time
+}
+
+/// Convert a JSON value into a newly allocated `cef_value_t`
+pub unsafe fn json_to_cef_value(value: &Value) -> *mut cef_value_t {
+    let cef_value = cef_value_create();
+    match value {
+        Value::Null => {
+            (*cef_value).set_null.expect("set_null is a function")(cef_value);
+        }
+        Value::Bool(b) => {
+            (*cef_value).set_bool.expect("set_bool is a function")(cef_value, *b as i32);
+        }
+        Value::Number(n) => match n.as_i64() {
+            Some(i) if i >= i32::min_value() as i64 && i <= i32::max_value() as i64 => {
+                (*cef_value).set_int.expect("set_int is a function")(cef_value, i as i32);
+            }
+            _ => {
+                let d = n.as_f64().unwrap_or(0.0);
+                (*cef_value).set_double.expect("set_double is a function")(cef_value, d);
+            }
+        },
+        Value::String(s) => {
+            let mut cef_s = to_cef_string(s);
+            (*cef_value).set_string.expect("set_string is a function")(cef_value, &cef_s);
+            cef_string_utf16_clear(&mut cef_s);
+        }
+        Value::Array(values) => {
+            let list = json_to_cef_list(values);
+            (*cef_value).set_list.expect("set_list is a function")(cef_value, list);
+        }
+        Value::Object(map) => {
+            let dictionary = json_to_cef_dictionary(map);
+            (*cef_value).set_dictionary.expect("set_dictionary is a function")(cef_value, dictionary);
+        }
+    }
+    cef_value
+}
+
+/// Convert a JSON array into a newly allocated `cef_list_value_t`
+pub unsafe fn json_to_cef_list(values: &[Value]) -> *mut cef_list_value_t {
+    let list = cef_list_value_create();
+    (*list).set_size.expect("set_size is a function")(list, values.len() as u64);
+    for (i, value) in values.iter().enumerate() {
+        let cef_value = json_to_cef_value(value);
+        (*list).set_value.expect("set_value is a function")(list, i as u64, cef_value);
+    }
+    list
+}
+
+/// Convert a JSON object into a newly allocated `cef_dictionary_value_t`
+pub unsafe fn json_to_cef_dictionary(map: &Map<String, Value>) -> *mut cef_dictionary_value_t {
+    let dictionary = cef_dictionary_value_create();
+    for (key, value) in map.iter() {
+        let mut cef_key = to_cef_string(key);
+        let cef_value = json_to_cef_value(value);
+        (*dictionary).set_value.expect("set_value is a function")(dictionary, &cef_key, cef_value);
+        cef_string_utf16_clear(&mut cef_key);
+    }
+    dictionary
+}
+
+/// Convert a `cef_value_t` into JSON. Binary values aren't representable and
+/// become `null`
+pub unsafe fn cef_value_to_json(value: *mut cef_value_t) -> Value {
+    if value.is_null() {
+        return Value::Null;
+    }
+
+    #[allow(non_upper_case_globals)]
+    match (*value).get_type.expect("get_type is a function")(value) {
+        cef_value_type_t_VTYPE_BOOL => {
+            Value::Bool((*value).get_bool.expect("get_bool is a function")(value) == 1)
+        }
+        cef_value_type_t_VTYPE_INT => {
+            Value::from((*value).get_int.expect("get_int is a function")(value))
+        }
+        cef_value_type_t_VTYPE_DOUBLE => {
+            let d = (*value).get_double.expect("get_double is a function")(value);
+            Number::from_f64(d).map(Value::Number).unwrap_or(Value::Null)
+        }
+        cef_value_type_t_VTYPE_STRING => {
+            Value::String(from_cef_userfree((*value).get_string.expect("get_string is a function")(value)))
+        }
+        cef_value_type_t_VTYPE_LIST => {
+            let list = (*value).get_list.expect("get_list is a function")(value);
+            let json = cef_list_to_json(list);
+            release_list(list);
+            json
+        }
+        cef_value_type_t_VTYPE_DICTIONARY => {
+            let dictionary = (*value).get_dictionary.expect("get_dictionary is a function")(value);
+            let json = cef_dictionary_to_json(dictionary);
+            release_dictionary(dictionary);
+            json
+        }
+        _ => Value::Null,
+    }
+}
+
+/// Convert a `cef_list_value_t` into a JSON array
+pub unsafe fn cef_list_to_json(list: *mut cef_list_value_t) -> Value {
+    if list.is_null() {
+        return Value::Null;
+    }
+    let size = (*list).get_size.expect("get_size is a function")(list);
+    let mut values: Vec<Value> = Vec::with_capacity(size as usize);
+    for i in 0..size {
+        let value = (*list).get_value.expect("get_value is a function")(list, i);
+        values.push(cef_value_to_json(value));
+        release_value(value);
+    }
+    Value::Array(values)
+}
+
+/// Convert a `cef_dictionary_value_t` into a JSON object
+pub unsafe fn cef_dictionary_to_json(dictionary: *mut cef_dictionary_value_t) -> Value {
+    if dictionary.is_null() {
+        return Value::Null;
+    }
+    let keys = cef_string_list_alloc();
+    (*dictionary).get_keys.expect("get_keys is a function")(dictionary, keys);
+    let mut map: Map<String, Value> = Map::new();
+    for key in from_cef_string_list(keys) {
+        let mut cef_key = to_cef_string(&key);
+        let value = (*dictionary).get_value.expect("get_value is a function")(dictionary, &cef_key);
+        map.insert(key, cef_value_to_json(value));
+        release_value(value);
+        cef_string_utf16_clear(&mut cef_key);
+    }
+    cef_string_list_free(keys);
+    Value::Object(map)
+}
+
+unsafe fn release_value(value: *mut cef_value_t) {
+    if value != null_mut() {
+        (*value).base.release.expect("release is a function")(&mut (*value).base);
+    }
+}
+
+unsafe fn release_list(list: *mut cef_list_value_t) {
+    if list != null_mut() {
+        (*list).base.release.expect("release is a function")(&mut (*list).base);
+    }
+}
+
+unsafe fn release_dictionary(dictionary: *mut cef_dictionary_value_t) {
+    if dictionary != null_mut() {
+        (*dictionary).base.release.expect("release is a function")(&mut (*dictionary).base);
+    }
+}
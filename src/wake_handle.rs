@@ -0,0 +1,88 @@
+/// Becomes ready whenever CEF schedules work or the pump is woken, so that
+/// CEF can be plugged into an existing event loop. On unix this is the read
+/// end of a socket pair that becomes readable, on Windows a manual-reset
+/// event that becomes signalled. It's cleared again by
+/// `Cef::do_message_loop_work`. Work that CEF schedules with a delay signals
+/// the handle right away, so also wait no longer than `Cef::time_until_work`
+#[cfg(unix)]
+pub struct WakeHandle {
+    reader: std::os::unix::net::UnixStream,
+    writer: std::os::unix::net::UnixStream,
+}
+
+#[cfg(unix)]
+impl WakeHandle {
+    pub(crate) fn new() -> WakeHandle {
+        let (reader, writer) = std::os::unix::net::UnixStream::pair().expect("can create a socket pair");
+        reader.set_nonblocking(true).expect("can make the socket non-blocking");
+        writer.set_nonblocking(true).expect("can make the socket non-blocking");
+        WakeHandle { reader, writer }
+    }
+
+    pub(crate) fn signal(&self) {
+        use std::io::Write;
+        // if the buffer is full, it's already readable
+        let _ = (&self.writer).write(&[1]);
+    }
+
+    pub(crate) fn clear(&self) {
+        use std::io::Read;
+        let mut buf = [0u8; 64];
+        while let Ok(n) = (&self.reader).read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl std::os::unix::io::AsRawFd for WakeHandle {
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+#[cfg(windows)]
+pub struct WakeHandle {
+    event: winapi::um::winnt::HANDLE,
+}
+
+// events can be signalled and waited on from any thread
+#[cfg(windows)]
+unsafe impl Send for WakeHandle {}
+#[cfg(windows)]
+unsafe impl Sync for WakeHandle {}
+
+#[cfg(windows)]
+impl WakeHandle {
+    pub(crate) fn new() -> WakeHandle {
+        let event = unsafe {
+            winapi::um::synchapi::CreateEventW(std::ptr::null_mut(), 1, 0, std::ptr::null())
+        };
+        assert!(!event.is_null(), "can create an event");
+        WakeHandle { event }
+    }
+
+    pub(crate) fn signal(&self) {
+        unsafe { winapi::um::synchapi::SetEvent(self.event) };
+    }
+
+    pub(crate) fn clear(&self) {
+        unsafe { winapi::um::synchapi::ResetEvent(self.event) };
+    }
+}
+
+#[cfg(windows)]
+impl std::os::windows::io::AsRawHandle for WakeHandle {
+    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+        self.event as std::os::windows::io::RawHandle
+    }
+}
+
+#[cfg(windows)]
+impl Drop for WakeHandle {
+    fn drop(&mut self) {
+        unsafe { winapi::um::handleapi::CloseHandle(self.event) };
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::Mutex;
+use std::time::Instant;
+
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoopProxy};
+use winit::window::Window;
+
+use super::browser::Browser;
+use super::{Cef, MessageLoopMode};
+
+impl Cef {
+    /// Create a browser filling the client area of a winit window. Pass the
+    /// window's events on to `Browser::handle_window_event` to keep it sized.
+    /// On Linux, CEF needs an X11 window, so winit must not pick Wayland;
+    /// i.e. set `WINIT_UNIX_BACKEND=x11`
+    pub fn create_browser_in(&mut self, window: &Window, url: &str) -> Result<Browser, Box<dyn std::error::Error>> {
+        #[cfg(target_os = "linux")]
+        {
+            use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+            if let RawWindowHandle::Wayland(_) = window.raw_window_handle() {
+                return Err(Box::from(
+                    "CEF needs an X11 window, run winit with `WINIT_UNIX_BACKEND=x11`",
+                ));
+            }
+        }
+
+        let size = window.inner_size();
+        self.create_browser("cef", window, url, size.width as i32, size.height as i32, None)
+    }
+
+    /// Do CEF's work from a winit event loop. Call this for every event;
+    /// it sets the control flow to wake up again when CEF next wants to do
+    /// work. winit dispatches the window messages itself, so this only pumps
+    /// CEF. Requires `MessageLoopMode::ExternalPump`
+    pub fn handle_winit_event<T>(&self, event: &Event<T>, control_flow: &mut ControlFlow) {
+        debug_assert_eq!(self.mode, MessageLoopMode::ExternalPump);
+
+        if let Event::MainEventsCleared = event {
+            if self.should_do_work() {
+                self.do_message_loop_work();
+            }
+        }
+
+        if let ControlFlow::Exit = control_flow {
+            return;
+        }
+        *control_flow = match self.time_until_work() {
+            Some(wait) => ControlFlow::WaitUntil(Instant::now() + wait),
+            None => ControlFlow::Wait,
+        };
+    }
+
+    /// Wake the winit event loop by sending `event` through `proxy` whenever
+    /// CEF schedules work, so that work that's scheduled while the loop is
+    /// waiting gets done on time
+    pub fn wake_winit_event_loop<T: Clone + Send + 'static>(&self, proxy: EventLoopProxy<T>, event: T) {
+        let proxy = Mutex::new(proxy);
+        self.set_wake_callback(move || {
+            // fails once the event loop is gone, which is fine
+            let _ = proxy
+                .lock()
+                .expect("event loop proxy isn't poisoned")
+                .send_event(event.clone());
+        });
+    }
+}
+
+impl Browser {
+    /// Follow the size and scale factor of the winit window this browser
+    /// was created in
+    pub fn handle_window_event(&self, event: &WindowEvent) {
+        match event {
+            WindowEvent::Resized(size) => {
+                self.resize(size.width as i32, size.height as i32);
+            }
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                self.notify_screen_info_changed();
+                self.resize(new_inner_size.width as i32, new_inner_size.height as i32);
+            }
+            _ => {}
+        }
+    }
+}